@@ -5,11 +5,7 @@ use scylla::{
     client::{session::Session, session_builder::SessionBuilder},
     frame::Compression,
 };
-use std::{
-    collections::HashMap,
-    sync::Arc,
-    time::{Duration, Instant},
-};
+use std::{collections::HashMap, sync::Arc, time::Duration};
 use tokio::sync::mpsc::UnboundedSender;
 use windsock::{Bench, BenchParameters, BenchTask, Profiling, Report};
 
@@ -88,9 +84,7 @@ impl Bench for CassandraBench {
             docker_compose("benches/windsock/config/cassandra-1-docker-compose.yaml");
         let address = "127.0.0.1:9042";
 
-        self.execute_run(address, &parameters).await;
-
-        Ok(())
+        self.execute_run(address, &parameters).await
     }
 
     async fn run_bencher(
@@ -112,27 +106,10 @@ impl Bench for CassandraBench {
                 .unwrap(),
         );
 
-        let tasks = BenchTaskCassandra { session }
-            .spawn_tasks(reporter.clone(), parameters.operations_per_second)
+        // the tasks have completed once run_bench returns, so the database they connect to can be safely dropped
+        BenchTaskCassandra { session }
+            .run_bench(&parameters, reporter)
             .await;
-
-        let start = Instant::now();
-        reporter.send(Report::Start).unwrap();
-
-        for _ in 0..parameters.runtime_seconds {
-            let second = Instant::now();
-            tokio::time::sleep(Duration::from_secs(1)).await;
-            reporter
-                .send(Report::SecondPassed(second.elapsed()))
-                .unwrap();
-        }
-
-        reporter.send(Report::FinishedIn(start.elapsed())).unwrap();
-
-        // make sure the tasks complete before we drop the database they are connecting to
-        for task in tasks {
-            task.await.unwrap();
-        }
     }
 }
 
@@ -0,0 +1,157 @@
+use anyhow::{Error, Result, anyhow};
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeSet, fmt::Display, str::FromStr};
+
+/// The number of cores that fit in a `cpu_set_t`, cores at or above this can not be pinned to
+#[cfg(target_os = "linux")]
+const MAX_CPUS: usize = 8 * std::mem::size_of::<libc::cpu_set_t>();
+
+/// A set of CPU cores that a process can be pinned to, parsed from a list such as `0-3,6`
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CpuSet(BTreeSet<usize>);
+
+impl CpuSet {
+    pub fn new(cpus: impl IntoIterator<Item = usize>) -> Self {
+        CpuSet(cpus.into_iter().collect())
+    }
+
+    pub(crate) fn overlaps(&self, other: &CpuSet) -> bool {
+        !self.0.is_disjoint(&other.0)
+    }
+
+    /// Pins the calling thread to the cores in this set.
+    /// Threads spawned afterwards inherit the pinning, so call this before creating the async runtime to pin the whole process.
+    #[cfg(target_os = "linux")]
+    pub(crate) fn pin_current_thread(&self) -> Result<()> {
+        // SAFETY: cpu_set_t is a plain bitmask for which all zeroes is a valid, empty value
+        let mut set: libc::cpu_set_t = unsafe { std::mem::zeroed() };
+        for &cpu in &self.0 {
+            if cpu >= MAX_CPUS {
+                return Err(anyhow!(
+                    "Cannot pin to CPU {cpu}, only CPUs below {MAX_CPUS} are supported"
+                ));
+            }
+            // SAFETY: cpu was checked to be within the bounds of the set
+            unsafe { libc::CPU_SET(cpu, &mut set) };
+        }
+        // SAFETY: set is a valid cpu_set_t of the size passed, a pid of 0 refers to the calling thread
+        let result =
+            unsafe { libc::sched_setaffinity(0, std::mem::size_of::<libc::cpu_set_t>(), &set) };
+        if result == 0 {
+            Ok(())
+        } else {
+            Err(anyhow!(
+                "Failed to pin to CPUs {self}: {}",
+                std::io::Error::last_os_error()
+            ))
+        }
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    pub(crate) fn pin_current_thread(&self) -> Result<()> {
+        Err(anyhow!(
+            "Failed to pin to CPUs {self}: pinning to CPUs is only supported on linux"
+        ))
+    }
+}
+
+/// Allows the calling thread to run on every core, undoing any pinning inherited from the parent process.
+/// Like [`CpuSet::pin_current_thread`] this must be called before creating the async runtime to apply to the whole process.
+#[cfg(target_os = "linux")]
+pub(crate) fn unpin_current_thread() -> Result<()> {
+    // SAFETY: sysconf has no preconditions
+    let configured = unsafe { libc::sysconf(libc::_SC_NPROCESSORS_CONF) };
+    let configured = usize::try_from(configured).unwrap_or(MAX_CPUS).max(1);
+    CpuSet::new(0..configured.min(MAX_CPUS)).pin_current_thread()
+}
+
+/// Pinning is not supported so there is nothing to undo
+#[cfg(not(target_os = "linux"))]
+pub(crate) fn unpin_current_thread() -> Result<()> {
+    Ok(())
+}
+
+impl FromStr for CpuSet {
+    type Err = Error;
+
+    fn from_str(text: &str) -> Result<Self> {
+        let invalid = || anyhow!("Invalid CPU list {text:?}, expected a list such as `0-3,6`");
+        let parse_cpu = |cpu: &str| cpu.trim().parse::<usize>().map_err(|_| invalid());
+
+        let mut cpus = BTreeSet::new();
+        for part in text.split(',') {
+            match part.split_once('-') {
+                Some((first, last)) => {
+                    let (first, last) = (parse_cpu(first)?, parse_cpu(last)?);
+                    if first > last {
+                        return Err(invalid());
+                    }
+                    cpus.extend(first..=last);
+                }
+                None => {
+                    cpus.insert(parse_cpu(part)?);
+                }
+            }
+        }
+        Ok(CpuSet(cpus))
+    }
+}
+
+/// Displays the set in the same form it is parsed from, with consecutive cores collapsed into ranges
+impl Display for CpuSet {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut ranges: Vec<(usize, usize)> = vec![];
+        for &cpu in &self.0 {
+            match ranges.last_mut() {
+                Some((_, last)) if *last + 1 == cpu => *last = cpu,
+                _ => ranges.push((cpu, cpu)),
+            }
+        }
+        for (i, (first, last)) in ranges.into_iter().enumerate() {
+            if i > 0 {
+                write!(f, ",")?;
+            }
+            if first == last {
+                write!(f, "{first}")?;
+            } else {
+                write!(f, "{first}-{last}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_and_display() {
+        for (text, cpus, display) in [
+            ("0", vec![0], "0"),
+            ("0-3,6", vec![0, 1, 2, 3, 6], "0-3,6"),
+            ("6, 0-3", vec![0, 1, 2, 3, 6], "0-3,6"),
+            ("1,2,3,5,7-8", vec![1, 2, 3, 5, 7, 8], "1-3,5,7-8"),
+            ("4-4,2-5", vec![2, 3, 4, 5], "2-5"),
+        ] {
+            let parsed: CpuSet = text.parse().unwrap();
+            assert_eq!(parsed, CpuSet::new(cpus), "{text:?}");
+            assert_eq!(parsed.to_string(), display, "{text:?}");
+            assert_eq!(display.parse::<CpuSet>().unwrap(), parsed, "{text:?}");
+        }
+    }
+
+    #[test]
+    fn parse_errors() {
+        for text in ["", "3-1", "a", "1-", "-1", "1,,2", "1-2-3", "0x1"] {
+            assert!(text.parse::<CpuSet>().is_err(), "{text:?}");
+        }
+    }
+
+    #[test]
+    fn overlaps() {
+        let set = CpuSet::new([0, 1, 2]);
+        assert!(set.overlaps(&CpuSet::new([2, 3])));
+        assert!(!set.overlaps(&CpuSet::new([3, 4])));
+    }
+}
@@ -1,7 +1,10 @@
-use crate::cli::RunArgs;
+use crate::affinity::CpuSet;
+use crate::capacity::{CapacitySearch, DEFAULT_START_RATE, Slo};
+use crate::cli::{CapacityArgs, OperationsPerSecond, RunArgs, SectionArgs, TableFormat};
+use crate::load_profile::LoadProfile;
 use crate::report::{Report, ReportArchive, report_builder};
 use crate::tables::ReportColumn;
-use anyhow::Result;
+use anyhow::{Context, Result, anyhow};
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
 use std::collections::{HashMap, HashSet};
@@ -32,20 +35,98 @@ impl<ResourcesRequired, Resources> BenchState<ResourcesRequired, Resources> {
         }
     }
 
+    /// Runs the bench and displays its results, or searches for the capacity of the bench when `--find-capacity` is set.
     pub async fn orchestrate(
         &mut self,
         args: &RunArgs,
         running_in_release: bool,
         cloud_resources: Option<Resources>,
-    ) {
+    ) -> Result<()>
+    where
+        Resources: Clone,
+    {
+        match Slo::from_args(&args.capacity) {
+            Some(slo) => {
+                self.find_capacity(args, &slo, running_in_release, cloud_resources)
+                    .await
+            }
+            None => {
+                self.orchestrate_once(args, running_in_release, cloud_resources)
+                    .await?;
+                display_results(&self.run_tags(args).get_name())
+            }
+        }
+    }
+
+    /// Runs the bench once at each rate chosen by a [`CapacitySearch`], then stores and displays the results of the bench at the capacity found.
+    /// The results of each probe are not displayed, only whether they met the SLO.
+    async fn find_capacity(
+        &mut self,
+        args: &RunArgs,
+        slo: &Slo,
+        running_in_release: bool,
+        cloud_resources: Option<Resources>,
+    ) -> Result<()>
+    where
+        Resources: Clone,
+    {
+        let mut search = CapacitySearch::new(args.rate().unwrap_or(DEFAULT_START_RATE));
+        while let Some(rate) = search.next_rate() {
+            let probe_args = RunArgs {
+                operations_per_second: Some(OperationsPerSecond::Sweep(vec![rate])),
+                capacity: CapacityArgs::default(),
+                ..args.clone()
+            };
+            self.orchestrate_once(&probe_args, running_in_release, cloud_resources.clone())
+                .await?;
+            let probe = ReportArchive::load(&self.run_tags(&probe_args).get_name())?;
+            let violation = slo.violation(&probe, rate);
+            match &violation {
+                None => println!("{rate} ops/s met the SLO"),
+                Some(violation) => println!("{rate} ops/s violated the SLO: {violation}"),
+            }
+            search.record(rate, violation);
+        }
+
         let name = self.tags.get_name();
-        println!("Running {:?}", name);
+        println!("Capacity search of {name:?} complete");
+        search.results(&self.tags)?.save();
+        display_results(&name)
+    }
+
+    async fn orchestrate_once(
+        &mut self,
+        args: &RunArgs,
+        running_in_release: bool,
+        cloud_resources: Option<Resources>,
+    ) -> Result<()> {
+        let run = self.orchestrate_inner(args, running_in_release, cloud_resources);
+        match args.timeout_seconds {
+            Some(seconds) => {
+                let limit = Duration::from_secs(seconds);
+                tokio::time::timeout(limit, run)
+                    .await
+                    .unwrap_or_else(|_| Err(TimedOut { limit }.into()))
+            }
+            None => run.await,
+        }
+    }
+
+    async fn orchestrate_inner(
+        &mut self,
+        args: &RunArgs,
+        running_in_release: bool,
+        cloud_resources: Option<Resources>,
+    ) -> Result<()> {
+        let run_tags = self.run_tags(args);
+        println!("Running {:?}", run_tags.get_name());
 
         let profilers_to_use = args.profilers.clone();
         let results_path = if !profilers_to_use.is_empty() {
+            // Under the tags of this run, so that each rate of a sweep and each repetition keeps its own profiler results
             let path = crate::data::windsock_path()
                 .join("profiler_results")
-                .join(&name);
+                .join(run_tags.get_file_name());
             std::fs::create_dir_all(&path).unwrap();
             path
         } else {
@@ -63,8 +144,7 @@ impl<ResourcesRequired, Resources> BenchState<ResourcesRequired, Resources> {
                     },
                     BenchParameters::from_args(args),
                 )
-                .await
-                .unwrap();
+                .await?;
         } else {
             self.bench
                 .orchestrate_local(
@@ -75,22 +155,42 @@ impl<ResourcesRequired, Resources> BenchState<ResourcesRequired, Resources> {
                     },
                     BenchParameters::from_args(args),
                 )
-                .await
-                .unwrap();
+                .await?;
         }
 
-        crate::tables::display_results_table(&[ReportColumn {
-            baseline: ReportArchive::load_baseline(&name).unwrap(),
-            current: ReportArchive::load(&name).unwrap(),
-        }]);
+        if args.sweep_rate().is_some() || args.repetition.is_some() {
+            // The bencher stores its results under the tags of the bench, move them to the tags of this run
+            ReportArchive::retag(&self.tags.get_name(), run_tags)?;
+        }
+        Ok(())
+    }
+
+    /// The tags the results of a run with these args are stored under.
+    /// When the run is one rate of a sweep the rate is included in the tags, as is the repetition of `--repeat`.
+    pub fn run_tags(&self, args: &RunArgs) -> Tags {
+        let mut tags = match args.sweep_rate() {
+            Some(rate) => self.tags.with_operations_per_second(rate),
+            None => self.tags.clone(),
+        };
+        if let Some(repetition) = args.repetition {
+            tags.0
+                .insert(REPETITION_TAG.to_owned(), repetition.to_string());
+        }
+        tags
     }
 
-    pub async fn run(&mut self, args: &RunArgs, running_in_release: bool, resources: &str) {
+    pub async fn run(
+        &mut self,
+        args: &RunArgs,
+        running_in_release: bool,
+        resources: &str,
+    ) -> Result<()> {
         let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
         let process = tokio::spawn(report_builder(
             self.tags.clone(),
             rx,
-            args.operations_per_second,
+            args.load_profile(),
+            self.bencher_cpus(args),
             running_in_release,
         ));
 
@@ -98,7 +198,14 @@ impl<ResourcesRequired, Resources> BenchState<ResourcesRequired, Resources> {
             .run_bencher(resources, BenchParameters::from_args(args), tx)
             .await;
 
-        process.await.unwrap();
+        process.await.unwrap().map(|_| ())
+    }
+
+    /// The cores to pin the bencher to, `--bencher-cpus` takes precedence over the cores specified by the bench
+    pub fn bencher_cpus(&self, args: &RunArgs) -> Option<CpuSet> {
+        args.bencher_cpus
+            .clone()
+            .or_else(|| self.bench.bencher_cpus())
     }
 
     // TODO: will return None when running in non-local setup
@@ -139,6 +246,12 @@ pub trait Bench {
         1
     }
 
+    /// The CPU cores to pin the bencher to, linux only.
+    /// By default the bencher is not pinned and may run on any core, which is overridden by `--bencher-cpus`.
+    fn bencher_cpus(&self) -> Option<CpuSet> {
+        None
+    }
+
     /// Windsock will call this method to orchestrate the bench in cloud mode.
     /// It must setup cloud resources to run the bench in a cloud and then start the bench returning the results on conclusion
     async fn orchestrate_cloud(
@@ -174,25 +287,43 @@ pub trait Bench {
         reporter: UnboundedSender<Report>,
     );
 
-    /// Call within `Bench::orchestrate_local` to call `Bench::run`
-    async fn execute_run(&self, resources: &str, bench_parameters: &BenchParameters) {
+    /// Call within `Bench::orchestrate_local` to call `Bench::run`.
+    /// Returns an error containing the output of the bencher if it fails, return it from `orchestrate_local` so that the failure is reported.
+    async fn execute_run(&self, resources: &str, bench_parameters: &BenchParameters) -> Result<()> {
         let name_and_resources = format!("{} {}", self.name(), resources);
-        let output = tokio::process::Command::new(std::env::current_exe().unwrap().as_os_str())
+        let output = tokio::process::Command::new(std::env::current_exe()?.as_os_str())
             .args(run_args_vec(name_and_resources, bench_parameters))
+            // stop the bencher if the bench is abandoned due to `--timeout-seconds`
+            .kill_on_drop(true)
             .output()
             .await
-            .unwrap();
-        if !output.status.success() {
-            let stdout = String::from_utf8(output.stdout).unwrap();
-            let stderr = String::from_utf8(output.stderr).unwrap();
-            panic!("Bench run failed:\nstdout:\n{stdout}\nstderr:\n{stderr}")
+            .context("Failed to start the bencher")?;
+        if output.status.success() {
+            Ok(())
+        } else {
+            Err(anyhow!(
+                "Bench run failed with {}\nstdout:\n{}\nstderr:\n{}",
+                output.status,
+                String::from_utf8_lossy(&output.stdout),
+                String::from_utf8_lossy(&output.stderr)
+            ))
         }
     }
 
     /// Call within `Bench::orchestrate_cloud` to determine how to invoke the uploaded windsock executable
     fn run_args(&self, resources: &str, bench_parameters: &BenchParameters) -> String {
-        let name_and_resources = format!("\"{} {}\"", self.name(), resources);
-        run_args_vec(name_and_resources, bench_parameters).join(" ")
+        let name_and_resources = format!("{} {}", self.name(), resources);
+        run_args_vec(name_and_resources, bench_parameters)
+            .into_iter()
+            .map(|arg| {
+                if arg.contains(char::is_whitespace) {
+                    format!("\"{arg}\"")
+                } else {
+                    arg
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(" ")
     }
 
     fn name(&self) -> String {
@@ -200,13 +331,57 @@ pub trait Bench {
     }
 }
 
+/// The error returned when a bench takes longer than `--timeout-seconds`
+#[derive(Debug)]
+pub(crate) struct TimedOut {
+    pub(crate) limit: Duration,
+}
+
+impl std::fmt::Display for TimedOut {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Bench timed out after {}",
+            crate::units::format_seconds(self.limit.as_secs())
+        )
+    }
+}
+
+impl std::error::Error for TimedOut {}
+
+/// Displays the results of the named bench in the last run, compared against the baseline
+fn display_results(name: &str) -> Result<()> {
+    crate::tables::display_results_table(
+        &[ReportColumn {
+            baseline: ReportArchive::load_baseline(name)?,
+            current: ReportArchive::load(name)?,
+        }],
+        TableFormat::Terminal,
+        &SectionArgs::default(),
+    );
+    Ok(())
+}
+
 fn run_args_vec(name_and_resources: String, bench_parameters: &BenchParameters) -> Vec<String> {
     let mut args = vec![];
     args.push("internal-run".to_owned());
     args.push("--bench-length-seconds".to_owned());
     args.push(bench_parameters.runtime_seconds.to_string());
 
-    if let Some(ops) = bench_parameters.operations_per_second {
+    if bench_parameters.warmup_seconds > 0 {
+        args.push("--warmup-seconds".to_owned());
+        args.push(bench_parameters.warmup_seconds.to_string());
+    }
+
+    if let Some(cpus) = &bench_parameters.bencher_cpus {
+        args.push("--bencher-cpus".to_owned());
+        args.push(cpus.to_string());
+    }
+
+    if bench_parameters.load_profile.is_varying() {
+        args.push("--load-profile".to_owned());
+        args.push(bench_parameters.load_profile.to_string());
+    } else if let Some(ops) = bench_parameters.operations_per_second {
         args.push("--operations-per-second".to_owned());
         args.push(ops.to_string());
     };
@@ -219,14 +394,24 @@ fn run_args_vec(name_and_resources: String, bench_parameters: &BenchParameters)
 /// Instructs the benches on how the bench should be run according to the users request.
 pub struct BenchParameters {
     pub runtime_seconds: u32,
+    /// How long to run the bench for before sending `Report::Start`, handled by [`BenchTask::run_bench`]
+    pub warmup_seconds: u32,
+    /// The constant rate to run the bench at, None if unlimited or if the rate varies according to `load_profile`
     pub operations_per_second: Option<u64>,
+    /// How the target rate changes over the course of the bench, pass this to [`BenchTask::spawn_tasks`] to follow it
+    pub load_profile: LoadProfile,
+    /// The cores requested by `--bencher-cpus`, passed on to `internal-run` where the bencher is pinned to them
+    pub bencher_cpus: Option<CpuSet>,
 }
 
 impl BenchParameters {
-    fn from_args(args: &RunArgs) -> Self {
+    pub(crate) fn from_args(args: &RunArgs) -> Self {
         BenchParameters {
             runtime_seconds: args.bench_length_seconds.unwrap_or(15),
-            operations_per_second: args.operations_per_second,
+            warmup_seconds: args.warmup_seconds.unwrap_or(0),
+            operations_per_second: args.rate(),
+            load_profile: args.load_profile(),
+            bencher_cpus: args.bencher_cpus.clone(),
         }
     }
 }
@@ -237,10 +422,23 @@ pub struct Profiling {
     pub profilers_to_use: Vec<String>,
 }
 
+/// The tag added to the results of each rate of a sweep of `--operations-per-second`
+pub(crate) const OPERATIONS_PER_SECOND_TAG: &str = "operations_per_second";
+/// The tag added to the results of each repetition of `--repeat`
+pub(crate) const REPETITION_TAG: &str = "repetition";
+
+/// Characters that have special meaning within a bench name or tag filter and so must be escaped with a `\` when they appear in a tag key or value.
+const NAME_SPECIAL_CHARS: [char; 4] = ['\\', ',', '=', '|'];
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub(crate) struct Tags(pub HashMap<String, String>);
 
 impl Tags {
+    /// Returns the name of the bench.
+    /// Tags are sorted by key and joined as `key=value` pairs separated by commas.
+    /// Any `\`, `,`, `=` or `|` within a key or value is escaped with a `\` so that the name can always be parsed back into its tags.
+    /// Any `*` or `?` within a value, or a `/` at the start of a value, is also escaped so that the name matches only this bench when used as a filter.
+    /// Likewise any `!`, `<` or `>` within a key, or a `(` or `)` at the start of a key, is escaped so that it is not parsed as a filter operator.
     pub fn get_name(&self) -> String {
         let mut result = String::new();
 
@@ -250,25 +448,86 @@ impl Tags {
             if !result.is_empty() {
                 write!(result, ",").unwrap();
             }
-            write!(result, "{key}={value}").unwrap();
+            write!(result, "{}={}", escape_key(key), escape_value(value)).unwrap();
         }
         result
     }
 
-    /// Does not handle invalid names, only use on internally generated names
-    pub fn from_name(name: &str) -> Self {
+    /// Returns these tags with the rate of a sweep of `--operations-per-second` added
+    pub fn with_operations_per_second(&self, rate: u64) -> Tags {
+        let mut tags = self.clone();
+        tags.0
+            .insert(OPERATIONS_PER_SECOND_TAG.to_owned(), rate.to_string());
+        tags
+    }
+
+    /// Returns a name that is safe to use as a file name on any filesystem.
+    /// Unless the name had to be truncated it can be converted back to the bench name via [`Tags::name_from_file_name`]
+    pub fn get_file_name(&self) -> String {
+        file_name_from_name(&self.get_name())
+    }
+
+    /// Parses a name created by [`Tags::get_name`]
+    pub fn from_name(name: &str) -> Result<Self> {
         let mut map = HashMap::new();
-        for tag in name.split(',') {
-            if tag.contains('=') {
-                let mut pair = tag.split('=');
-                let key = pair.next().unwrap().to_owned();
-                let value = pair.next().unwrap().to_owned();
-                map.insert(key, value);
+        for tag in split_unescaped(name, ',') {
+            let mut pair = split_unescaped(tag, '=');
+            let (Some(key), Some(value), None) = (pair.next(), pair.next(), pair.next()) else {
+                return Err(anyhow!(
+                    "Expected exactly one unescaped '=' in tag {tag:?} of bench name {name:?}"
+                ));
+            };
+            if map.insert(unescape(key)?, unescape(value)?).is_some() {
+                return Err(anyhow!(
+                    "The tag key {key:?} occurs multiple times in bench name {name:?}"
+                ));
+            }
+        }
+        Ok(Tags(map))
+    }
+
+    /// Converts a file name created by [`Tags::get_file_name`] back into the bench name.
+    /// Returns None if the file name was not created by [`Tags::get_file_name`] or if it was truncated, see [`is_hashed_file_name`].
+    pub fn name_from_file_name(file_name: &str) -> Option<String> {
+        let bytes = file_name.as_bytes();
+        let mut name = Vec::with_capacity(bytes.len());
+        let mut i = 0;
+        while i < bytes.len() {
+            if bytes[i] == b'%' {
+                let hex = std::str::from_utf8(bytes.get(i + 1..i + 3)?).ok()?;
+                name.push(u8::from_str_radix(hex, 16).ok()?);
+                i += 3;
+            } else if is_file_name_safe(bytes[i]) {
+                name.push(bytes[i]);
+                i += 1;
             } else {
-                panic!("tag without an '=' was found")
+                return None;
+            }
+        }
+        String::from_utf8(name).ok()
+    }
+
+    /// Ensures that the tags can be used to form a bench name that is usable from the CLI
+    pub(crate) fn validate(&self) -> Result<()> {
+        if self.0.is_empty() {
+            return Err(anyhow!("A bench must have at least one tag"));
+        }
+        for (key, value) in &self.0 {
+            if key.is_empty() {
+                return Err(anyhow!("The tag key for value {value:?} is empty"));
+            }
+            if value.is_empty() {
+                return Err(anyhow!("The tag value for key {key:?} is empty"));
+            }
+            for text in [key, value] {
+                if text.chars().any(|c| c.is_whitespace() || c.is_control()) {
+                    return Err(anyhow!(
+                        "The tag {key:?}={value:?} contains whitespace or control characters, these are not allowed as they are used to separate filters on the CLI"
+                    ));
+                }
             }
         }
-        Tags(map)
+        Ok(())
     }
 
     /// returns the set wise intersection of two `Tags`s
@@ -287,6 +546,170 @@ impl Tags {
     }
 }
 
+/// Escapes a tag key so that it is not mistaken for a filter operator, group or negation when used in a filter
+fn escape_key(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    for (i, c) in text.chars().enumerate() {
+        if NAME_SPECIAL_CHARS.contains(&c)
+            || matches!(c, '!' | '<' | '>')
+            || (i == 0 && matches!(c, '(' | ')'))
+        {
+            result.push('\\');
+        }
+        result.push(c);
+    }
+    result
+}
+
+/// Escapes a tag value so that it is matched exactly when used in a filter, rather than as a glob or regex
+fn escape_value(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    for (i, c) in text.chars().enumerate() {
+        if NAME_SPECIAL_CHARS.contains(&c) || matches!(c, '*' | '?') || (i == 0 && c == '/') {
+            result.push('\\');
+        }
+        result.push(c);
+    }
+    result
+}
+
+/// Removes the escaping added by [`escape_key`] and [`escape_value`]
+pub(crate) fn unescape(text: &str) -> Result<String> {
+    let mut result = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            match chars.next() {
+                Some(escaped) => result.push(escaped),
+                None => {
+                    return Err(anyhow!(
+                        "{text:?} ends with a '\\' that does not escape anything, use '\\\\' for a literal '\\'"
+                    ));
+                }
+            }
+        } else {
+            result.push(c);
+        }
+    }
+    Ok(result)
+}
+
+/// Splits `text` on every occurence of `separator` that is not escaped by a `\`
+pub(crate) fn split_unescaped(text: &str, separator: char) -> impl Iterator<Item = &str> {
+    let mut escaped = false;
+    text.split(move |c| {
+        if escaped {
+            escaped = false;
+            false
+        } else if c == '\\' {
+            escaped = true;
+            false
+        } else {
+            c == separator
+        }
+    })
+}
+
+/// File names are kept well below the 255 byte limit of most filesystems so that temporary suffixes can be appended
+const MAX_FILE_NAME_LEN: usize = 200;
+/// Separates a truncated file name from the hash of the full name, never produced by percent encoding
+const FILE_NAME_HASH_SEPARATOR: char = '+';
+
+/// Converts a bench name into a file name by percent encoding every byte that is not known to be safe on all filesystems.
+/// Uppercase letters are encoded too so that names differing only by case do not collide on case insensitive filesystems.
+/// Names that would exceed [`MAX_FILE_NAME_LEN`] are truncated and suffixed with a hash of the full name.
+pub(crate) fn file_name_from_name(name: &str) -> String {
+    let mut result = String::with_capacity(name.len());
+    // The length of result at the end of each encoded byte, so that truncation never splits an escape
+    let mut boundaries = vec![];
+    for byte in name.bytes() {
+        if is_file_name_safe(byte) {
+            result.push(byte as char);
+        } else {
+            write!(result, "%{byte:02X}").unwrap();
+        }
+        boundaries.push(result.len());
+    }
+    if result.len() > MAX_FILE_NAME_LEN {
+        let hash = format!("{FILE_NAME_HASH_SEPARATOR}{:016x}", fnv1a(name.as_bytes()));
+        let keep = boundaries
+            .into_iter()
+            .take_while(|x| *x <= MAX_FILE_NAME_LEN - hash.len())
+            .last()
+            .unwrap_or(0);
+        result.truncate(keep);
+        result.push_str(&hash);
+    }
+    result
+}
+
+/// Returns true if the file name was truncated by [`file_name_from_name`], the name can then only be recovered from the archive itself
+pub(crate) fn is_hashed_file_name(file_name: &str) -> bool {
+    file_name
+        .rsplit_once(FILE_NAME_HASH_SEPARATOR)
+        .is_some_and(|(_, hash)| {
+            hash.len() == 16
+                && hash
+                    .bytes()
+                    .all(|x| x.is_ascii_digit() || matches!(x, b'a'..=b'f'))
+        })
+}
+
+/// A hash that is stable across platforms and rust versions, unlike the hashers of std
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ *byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+fn is_file_name_safe(byte: u8) -> bool {
+    byte.is_ascii_lowercase()
+        || byte.is_ascii_digit()
+        || matches!(byte, b'-' | b'_' | b'.' | b'=' | b',')
+}
+
+/// How often a task waiting for its next operation rechecks the target rate of a varying load profile
+const RATE_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Waits until the next operation of a task is due, each task is allocated an equal share of the current target rate.
+/// Returns the time the operation was due, which the following operation is scheduled from.
+async fn wait_for_operation(
+    load: &LoadProfile,
+    profile_start: Instant,
+    task_count: u64,
+    mut previous: Option<tokio::time::Instant>,
+) -> tokio::time::Instant {
+    loop {
+        let now = tokio::time::Instant::now();
+        let elapsed = now.into_std().saturating_duration_since(profile_start);
+        match load.rate_at(elapsed) {
+            None => return now,
+            Some(0) => {
+                // no operations are wanted right now, resume immediately once the rate increases instead of catching up
+                previous = None;
+                tokio::time::sleep(RATE_POLL_INTERVAL).await;
+            }
+            Some(ops) => {
+                let period = Duration::from_secs(1) * task_count as u32 / ops as u32;
+                let due = previous.map_or(now, |previous| previous + period);
+                if due <= now {
+                    if load.is_varying() {
+                        // the previous operation was scheduled at a lower rate, so do not catch up on the time since then
+                        return due.max(now.checked_sub(period).unwrap_or(now));
+                    }
+                    return due;
+                } else if load.is_varying() {
+                    // the rate may change before the operation is due
+                    tokio::time::sleep_until(due.min(now + RATE_POLL_INTERVAL)).await;
+                } else {
+                    tokio::time::sleep_until(due).await;
+                    return due;
+                }
+            }
+        }
+    }
+}
+
 /// An optional helper trait for defining benchmarks.
 ///
 /// Usually you have an async rust DB driver that you need to call across multiple tokio tasks
@@ -295,55 +718,190 @@ impl Tags {
 /// To use this helper:
 ///  1. implement `BenchTask` for a struct that contains the required db resources
 ///  2. have run_one_operation use those resources to perform a single operation
-///  3. call spawn_tasks on an instance of BenchTask, it will clone your BenchTask instance once for each task it generates
+///  3. call run_bench on an instance of BenchTask from `Bench::run_bencher`, it will clone your BenchTask instance once for each task it generates
+///
+/// Benches that need to control the run themselves can instead call spawn_tasks and then send the `Report`s that run_bench would.
+/// The load passed to spawn_tasks is either `BenchParameters::load_profile` or `BenchParameters::operations_per_second`.
+/// The target rate of a [`LoadProfile`] is followed from the time spawn_tasks returns, so send `Report::Start` immediately after.
 #[async_trait]
 pub trait BenchTask: Clone + Send + Sync + 'static {
     async fn run_one_operation(&self) -> Result<(), String>;
 
-    async fn spawn_tasks(
+    async fn spawn_tasks<L: Into<LoadProfile> + Send>(
         &self,
         reporter: UnboundedSender<Report>,
-        operations_per_second: Option<u64>,
+        load: L,
     ) -> Vec<JoinHandle<()>> {
-        let mut tasks = vec![];
-        // 100 is a generally nice amount of tasks to have, but if we have more tasks than OPS the throughput is very unstable
-        let task_count = operations_per_second.map(|x| x.min(100)).unwrap_or(100);
-
-        let allocated_time_per_op = operations_per_second
-            .map(|ops| (Duration::from_secs(1) * task_count as u32) / ops as u32);
-        for i in 0..task_count {
-            let task = self.clone();
-            let reporter = reporter.clone();
-            tasks.push(tokio::spawn(async move {
-                // spread load out over a second
-                tokio::time::sleep(Duration::from_nanos((1_000_000_000 / task_count) * i)).await;
-
-                let mut interval = allocated_time_per_op.map(tokio::time::interval);
-
-                loop {
-                    if let Some(interval) = &mut interval {
-                        interval.tick().await;
-                    }
+        spawn_tasks_with_warmup(self, reporter, load.into(), Duration::ZERO).await
+    }
 
-                    let operation_start = Instant::now();
-                    let report = match task.run_one_operation().await {
-                        Ok(()) => Report::QueryCompletedIn(operation_start.elapsed()),
-                        Err(message) => Report::QueryErrored {
-                            completed_in: operation_start.elapsed(),
-                            message,
-                        },
-                    };
-                    if reporter.send(report).is_err() {
-                        // The benchmark has completed and the reporter no longer wants to receive reports so just shutdown
-                        return;
-                    }
+    /// Runs the entire bench, call this from `Bench::run_bencher` once the resources needed by `run_one_operation` are set up.
+    ///
+    /// Spawns the tasks following `BenchParameters::load_profile`, runs them for the warmup without measuring,
+    /// measures for `BenchParameters::runtime_seconds` reporting each second as it passes
+    /// and then waits for the tasks to shutdown so that resources used by the tasks can be safely dropped afterwards.
+    async fn run_bench(&self, parameters: &BenchParameters, reporter: UnboundedSender<Report>) {
+        let warmup = Duration::from_secs(parameters.warmup_seconds as u64);
+        let tasks = spawn_tasks_with_warmup(
+            self,
+            reporter.clone(),
+            parameters.load_profile.clone(),
+            warmup,
+        )
+        .await;
+        tokio::time::sleep(warmup).await;
+
+        let start = Instant::now();
+        reporter.send(Report::Start).unwrap();
+
+        for _ in 0..parameters.runtime_seconds {
+            let second = Instant::now();
+            tokio::time::sleep(Duration::from_secs(1)).await;
+            reporter
+                .send(Report::SecondPassed(second.elapsed()))
+                .unwrap();
+        }
+
+        reporter.send(Report::FinishedIn(start.elapsed())).unwrap();
+
+        for task in tasks {
+            task.await.unwrap();
+        }
+    }
+}
+
+/// Spawns the tasks of [`BenchTask::spawn_tasks`], the load profile begins after the warmup.
+/// Returns once all tasks have started running, before the warmup.
+async fn spawn_tasks_with_warmup<T: BenchTask>(
+    bench_task: &T,
+    reporter: UnboundedSender<Report>,
+    load: LoadProfile,
+    warmup: Duration,
+) -> Vec<JoinHandle<()>> {
+    let mut tasks = vec![];
+    // 100 is a generally nice amount of tasks to have, but if we have more tasks than OPS the throughput is very unstable
+    let task_count = load.peak().map(|x| x.clamp(1, 100)).unwrap_or(100);
+    // the profile begins once all tasks have started running and the warmup is over
+    let profile_start = Instant::now() + Duration::from_secs(1) + warmup;
+
+    for i in 0..task_count {
+        let task = bench_task.clone();
+        let reporter = reporter.clone();
+        let load = load.clone();
+        tasks.push(tokio::spawn(async move {
+            // spread load out over a second
+            tokio::time::sleep(Duration::from_nanos((1_000_000_000 / task_count) * i)).await;
+
+            let mut previous_operation = None;
+
+            loop {
+                if load.peak().is_some() {
+                    previous_operation = Some(
+                        wait_for_operation(&load, profile_start, task_count, previous_operation)
+                            .await,
+                    );
+                }
+
+                let operation_start = Instant::now();
+                let report = match task.run_one_operation().await {
+                    Ok(()) => Report::QueryCompletedIn(operation_start.elapsed()),
+                    Err(message) => Report::QueryErrored {
+                        completed_in: operation_start.elapsed(),
+                        message,
+                    },
+                };
+                if reporter.send(report).is_err() {
+                    // The benchmark has completed and the reporter no longer wants to receive reports so just shutdown
+                    return;
                 }
-            }));
+            }
+        }));
+    }
+
+    // sleep until all tasks have started running
+    tokio::time::sleep(Duration::from_secs(1)).await;
+
+    tasks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tags(pairs: &[(&str, &str)]) -> Tags {
+        Tags(
+            pairs
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn name_round_trip() {
+        let tags = tags(&[("db", "kafka"), ("a,b", "c=d|e\\f"), ("path", "/tmp/*?")]);
+        let name = tags.get_name();
+        assert_eq!(name, r"a\,b=c\=d\|e\\f,db=kafka,path=\/tmp/\*\?");
+        assert_eq!(Tags::from_name(&name).unwrap().0, tags.0);
+    }
+
+    #[test]
+    fn from_name_errors() {
+        assert!(Tags::from_name("db").is_err());
+        assert!(Tags::from_name("db=a=b").is_err());
+        assert!(Tags::from_name("db=a,db=b").is_err());
+        assert!(Tags::from_name(r"db=a\").is_err());
+    }
+
+    #[test]
+    fn unescape_trailing_backslash() {
+        assert_eq!(unescape(r"a\,b\\").unwrap(), r"a,b\");
+        assert!(unescape(r"ab\").is_err());
+    }
+
+    #[test]
+    fn file_name_round_trip() {
+        for name in [
+            "db=kafka,topology=single",
+            r"a\,b=c/d",
+            "db=Foo",
+            "emoji=🦀",
+        ] {
+            let file_name = file_name_from_name(name);
+            assert!(!is_hashed_file_name(&file_name));
+            assert_eq!(Tags::name_from_file_name(&file_name).unwrap(), name);
         }
+    }
+
+    #[test]
+    fn file_name_case_insensitive_unique() {
+        let upper = file_name_from_name("db=Foo");
+        let lower = file_name_from_name("db=foo");
+        assert_eq!(upper, "db=%46oo");
+        assert_ne!(upper.to_lowercase(), lower.to_lowercase());
+    }
 
-        // sleep until all tasks have started running
-        tokio::time::sleep(Duration::from_secs(1)).await;
+    #[test]
+    fn file_name_not_created_by_windsock() {
+        assert_eq!(Tags::name_from_file_name("db=Foo"), None);
+        assert_eq!(Tags::name_from_file_name("db=foo~tmp123"), None);
+        assert_eq!(Tags::name_from_file_name("db=%4"), None);
+    }
 
-        tasks
+    #[test]
+    fn long_file_name_is_hashed() {
+        let long = format!("db={}", "/".repeat(300));
+        let file_name = file_name_from_name(&long);
+        assert!(file_name.len() <= MAX_FILE_NAME_LEN);
+        assert!(is_hashed_file_name(&file_name));
+        assert_eq!(Tags::name_from_file_name(&file_name), None);
+        assert!(!is_hashed_file_name(&format!("{file_name}~tmp123")));
+
+        // differing only after the truncation point still produces different file names
+        let other = format!("{long}x");
+        assert_ne!(file_name, file_name_from_name(&other));
+        // truncation never splits an escape
+        let prefix = file_name.split(FILE_NAME_HASH_SEPARATOR).next().unwrap();
+        assert!(prefix.ends_with("%2F"));
     }
 }
@@ -0,0 +1,449 @@
+use crate::{
+    bench::Tags,
+    charts::{MIN_SPARKLINE_WIDTH, STATS_WIDTH, series},
+    cli::SectionArgs,
+    filter::{Filter, query_from_name},
+    report::ReportArchive,
+    tables::{Color, NoteKind, ReportColumn, Row, Table, build_table},
+};
+use anyhow::{Context, Result, anyhow};
+use console::measure_text_width;
+use ratatui::{
+    DefaultTerminal, Frame,
+    crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind},
+    layout::{Constraint, Layout, Rect},
+    style::{Style, Stylize},
+    text::{Line, Span},
+    widgets::{Block, List, ListState, Paragraph, Tabs},
+};
+
+/// The set of results being browsed
+#[derive(Clone, Copy, PartialEq)]
+enum Run {
+    LastRun,
+    Baseline,
+}
+
+impl Run {
+    fn name(&self) -> &'static str {
+        match self {
+            Run::LastRun => "last run",
+            Run::Baseline => "baseline",
+        }
+    }
+
+    fn bench_names(&self) -> Vec<String> {
+        match self {
+            Run::LastRun => ReportArchive::reports_in_last_run(),
+            Run::Baseline => ReportArchive::reports_in_baseline(),
+        }
+    }
+}
+
+/// What is displayed for the selected bench
+#[derive(Clone, Copy, PartialEq)]
+enum View {
+    Results,
+    Charts,
+    Messages,
+}
+
+const VIEWS: [View; 3] = [View::Results, View::Charts, View::Messages];
+
+impl View {
+    fn title(&self) -> &'static str {
+        match self {
+            View::Results => "Results",
+            View::Charts => "Charts",
+            View::Messages => "Messages",
+        }
+    }
+}
+
+struct Browser {
+    run: Run,
+    compare_baseline: bool,
+    /// The text of the applied filter
+    filter: String,
+    /// The text of the filter being edited, None when not editing
+    filter_input: Option<String>,
+    /// The benches of the run that match the filter
+    names: Vec<String>,
+    list: ListState,
+    view: View,
+    scroll: u16,
+    /// The selected bench, None if no bench is selected or it failed to load
+    column: Option<ReportColumn>,
+    /// The table of the selected bench, built from the same rows as the `results` command
+    table: Option<Table>,
+    /// An error to display in the status bar
+    error: Option<String>,
+}
+
+/// Opens an interactive terminal UI for browsing the results in windsock_data.
+pub fn browse(filter: &str) -> Result<()> {
+    let mut browser = Browser {
+        run: Run::LastRun,
+        compare_baseline: true,
+        filter: String::new(),
+        filter_input: None,
+        names: vec![],
+        list: ListState::default(),
+        view: View::Results,
+        scroll: 0,
+        column: None,
+        table: None,
+        error: None,
+    };
+    browser.apply_filter(filter)?;
+
+    let mut terminal =
+        ratatui::try_init().context("browse must be run from an interactive terminal")?;
+    let result = browser.run(&mut terminal);
+    ratatui::restore();
+    result
+}
+
+impl Browser {
+    fn run(&mut self, terminal: &mut DefaultTerminal) -> Result<()> {
+        loop {
+            terminal.draw(|frame| self.draw(frame))?;
+            if let Event::Key(key) = event::read()? {
+                if key.kind == KeyEventKind::Press && !self.handle_key(key) {
+                    return Ok(());
+                }
+            }
+        }
+    }
+
+    /// Returns false when the browser should exit
+    fn handle_key(&mut self, key: KeyEvent) -> bool {
+        if let Some(input) = &mut self.filter_input {
+            match key.code {
+                KeyCode::Enter => {
+                    let input = input.clone();
+                    self.filter_input = None;
+                    if let Err(err) = self.apply_filter(&input) {
+                        self.error = Some(format!("{err:#}"));
+                    }
+                }
+                KeyCode::Esc => self.filter_input = None,
+                KeyCode::Backspace => {
+                    input.pop();
+                }
+                KeyCode::Char(c) => input.push(c),
+                _ => {}
+            }
+            return true;
+        }
+
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => return false,
+            KeyCode::Char('/') => {
+                self.error = None;
+                self.filter_input = Some(self.filter.clone());
+            }
+            KeyCode::Up | KeyCode::Char('k') => self.select_offset(-1),
+            KeyCode::Down | KeyCode::Char('j') => self.select_offset(1),
+            KeyCode::PageUp => self.scroll = self.scroll.saturating_sub(10),
+            KeyCode::PageDown => self.scroll = self.scroll.saturating_add(10),
+            KeyCode::Tab => {
+                let index = VIEWS.iter().position(|x| *x == self.view).unwrap();
+                self.view = VIEWS[(index + 1) % VIEWS.len()];
+                self.scroll = 0;
+            }
+            KeyCode::Char(c @ '1'..='3') => {
+                self.view = VIEWS[c as usize - '1' as usize];
+                self.scroll = 0;
+            }
+            KeyCode::Char('b') => {
+                self.compare_baseline = !self.compare_baseline;
+                self.load_selected();
+            }
+            KeyCode::Char('r') => {
+                self.run = match self.run {
+                    Run::LastRun => Run::Baseline,
+                    Run::Baseline => Run::LastRun,
+                };
+                self.refresh_names();
+            }
+            _ => {}
+        }
+        true
+    }
+
+    /// Applies the filter, leaving the current filter in place if it is invalid
+    fn apply_filter(&mut self, filter: &str) -> Result<()> {
+        Filter::from_query(&query_from_name(filter))
+            .with_context(|| format!("Failed to parse tag filter from {:?}", filter))?;
+        self.filter = filter.to_owned();
+        self.error = None;
+        self.refresh_names();
+        Ok(())
+    }
+
+    /// Reloads the benches matching the filter, keeping the same bench selected if it still matches
+    fn refresh_names(&mut self) {
+        let selected = self
+            .list
+            .selected()
+            .and_then(|i| self.names.get(i))
+            .cloned();
+        let filter = Filter::from_query(&query_from_name(&self.filter)).unwrap();
+        self.names = self
+            .run
+            .bench_names()
+            .into_iter()
+            .filter(|name| {
+                Tags::from_name(name)
+                    .map(|tags| filter.matches(&tags))
+                    .unwrap_or(false)
+            })
+            .collect();
+        let index = selected
+            .and_then(|selected| self.names.iter().position(|x| *x == selected))
+            .or(if self.names.is_empty() { None } else { Some(0) });
+        self.list.select(index);
+        self.load_selected();
+    }
+
+    fn select_offset(&mut self, offset: isize) {
+        if self.names.is_empty() {
+            return;
+        }
+        let index = self.list.selected().unwrap_or(0) as isize + offset;
+        self.list
+            .select(Some(index.clamp(0, self.names.len() as isize - 1) as usize));
+        self.scroll = 0;
+        self.load_selected();
+    }
+
+    fn load_selected(&mut self) {
+        self.column = None;
+        self.table = None;
+        let Some(name) = self.list.selected().and_then(|i| self.names.get(i)) else {
+            return;
+        };
+        let column = match self.run {
+            Run::LastRun if self.compare_baseline => ReportColumn::load_with_baseline(name),
+            Run::LastRun => ReportColumn::load(name),
+            Run::Baseline => ReportArchive::load_baseline(name).and_then(|x| {
+                Ok(ReportColumn {
+                    baseline: None,
+                    current: x.ok_or_else(|| anyhow!("The bench {name:?} has no baseline"))?,
+                })
+            }),
+        };
+        match column {
+            Ok(column) => {
+                let table_type = if column.baseline.is_some() {
+                    "Comparison against baseline"
+                } else {
+                    "Results"
+                };
+                self.table = Some(build_table(
+                    std::slice::from_ref(&column),
+                    table_type,
+                    &SectionArgs::default(),
+                ));
+                self.column = Some(column);
+            }
+            Err(err) => self.error = Some(format!("{err:#}")),
+        }
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let [filter_area, main_area, status_area] = Layout::vertical([
+            Constraint::Length(3),
+            Constraint::Min(0),
+            Constraint::Length(1),
+        ])
+        .areas(frame.area());
+        let [list_area, detail_area] =
+            Layout::horizontal([Constraint::Percentage(35), Constraint::Percentage(65)])
+                .areas(main_area);
+
+        let filter = match &self.filter_input {
+            Some(input) => Line::from(vec![Span::raw(input.as_str()), "█".slow_blink()]),
+            None if self.filter.is_empty() => Line::from("(all benches)".dim()),
+            None => Line::from(self.filter.as_str()),
+        };
+        let filter_block = Block::bordered().title(" Filter ");
+        let filter_block = if self.filter_input.is_some() {
+            filter_block.yellow()
+        } else {
+            filter_block
+        };
+        frame.render_widget(Paragraph::new(filter).block(filter_block), filter_area);
+
+        let list = List::new(self.names.iter().map(|x| x.as_str()))
+            .block(Block::bordered().title(format!(
+                " Benches in {} ({}) ",
+                self.run.name(),
+                self.names.len()
+            )))
+            .highlight_style(Style::new().reversed())
+            .highlight_symbol("> ");
+        frame.render_stateful_widget(list, list_area, &mut self.list);
+
+        self.draw_detail(frame, detail_area);
+
+        let status = match &self.error {
+            Some(error) => Line::from(error.as_str().red()),
+            None if self.filter_input.is_some() => {
+                Line::from("enter apply filter  esc cancel".dim())
+            }
+            None => Line::from(
+                format!(
+                    "/ filter  ↑↓ select  tab view  pgup/pgdn scroll  b baseline: {}  r switch run  q quit",
+                    if self.compare_baseline { "on" } else { "off" }
+                )
+                .dim(),
+            ),
+        };
+        frame.render_widget(Paragraph::new(status), status_area);
+    }
+
+    fn draw_detail(&self, frame: &mut Frame, area: Rect) {
+        let [tabs_area, content_area] =
+            Layout::vertical([Constraint::Length(1), Constraint::Min(0)]).areas(area);
+        let tabs = Tabs::new(
+            VIEWS
+                .iter()
+                .enumerate()
+                .map(|(i, x)| format!("{} {}", i + 1, x.title())),
+        )
+        .select(VIEWS.iter().position(|x| *x == self.view).unwrap())
+        .highlight_style(Style::new().bold().reversed());
+        frame.render_widget(tabs, tabs_area);
+
+        let lines = match (&self.column, &self.table) {
+            (Some(column), Some(table)) => match self.view {
+                View::Results => table_lines(table),
+                View::Charts => chart_lines(column, content_area.width.saturating_sub(2)),
+                View::Messages => message_lines(table),
+            },
+            _ => vec![Line::from("No bench selected".dim())],
+        };
+        frame.render_widget(
+            Paragraph::new(lines)
+                .block(Block::bordered())
+                .scroll((self.scroll, 0)),
+            content_area,
+        );
+    }
+}
+
+fn table_lines(table: &Table) -> Vec<Line<'static>> {
+    let legend_width = table
+        .rows
+        .iter()
+        .filter_map(|row| match row {
+            Row::Heading(_) => None,
+            Row::ColumnNames { legend, .. } | Row::Measurements { legend, .. } => {
+                Some(measure_text_width(legend))
+            }
+        })
+        .max()
+        .unwrap_or(0);
+    let mut column_widths = vec![0; table.column_count()];
+    for row in &table.rows {
+        match row {
+            Row::Heading(_) => {}
+            Row::ColumnNames { names, .. } => {
+                for (width, name) in column_widths.iter_mut().zip(names) {
+                    *width = (*width).max(measure_text_width(name));
+                }
+            }
+            Row::Measurements { measurements, .. } => {
+                for (width, measurement) in column_widths.iter_mut().zip(measurements) {
+                    *width = (*width).max(
+                        measure_text_width(&measurement.value)
+                            + measure_text_width(&measurement.comparison)
+                            + 1,
+                    );
+                }
+            }
+        }
+    }
+
+    let mut lines = vec![];
+    for row in &table.rows {
+        match row {
+            Row::Heading(heading) => lines.push(Line::from(heading.clone().bold())),
+            Row::ColumnNames { legend, names } => {
+                let mut spans = vec![Span::raw(format!("{legend:legend_width$}"))];
+                for (name, width) in names.iter().zip(&column_widths) {
+                    spans.push(Span::raw(format!("  {name:>width$}")).bold());
+                }
+                lines.push(Line::from(spans));
+            }
+            Row::Measurements {
+                legend,
+                measurements,
+            } => {
+                let mut spans = vec![Span::raw(format!("{legend:legend_width$}"))];
+                for (measurement, width) in measurements.iter().zip(&column_widths) {
+                    let comparison = Span::raw(measurement.comparison.clone());
+                    let comparison = match measurement.color {
+                        Color::Good => comparison.green(),
+                        Color::Bad => comparison.red(),
+                        Color::Neutral => comparison,
+                    };
+                    let value_width =
+                        width.saturating_sub(measure_text_width(&measurement.comparison) + 1);
+                    spans.push(Span::raw(format!("  {:>value_width$} ", measurement.value)));
+                    spans.push(comparison);
+                }
+                lines.push(Line::from(spans));
+            }
+        }
+    }
+    lines
+}
+
+fn chart_lines(column: &ReportColumn, width: u16) -> Vec<Line<'static>> {
+    let series = series(column);
+    if series.is_empty() {
+        return vec![Line::from("No values were recorded each second".dim())];
+    }
+    let title_width = series
+        .iter()
+        .map(|x| measure_text_width(&x.title))
+        .max()
+        .unwrap()
+        .max("baseline".len());
+    let sparkline_width = (width as usize)
+        .saturating_sub(title_width + STATS_WIDTH + 4)
+        .max(MIN_SPARKLINE_WIDTH);
+
+    let mut lines = vec![];
+    for series in &series {
+        let (current, baseline) = series.lines(title_width, sparkline_width);
+        lines.push(Line::from(current));
+        if let Some(baseline) = baseline {
+            lines.push(Line::from(baseline.dim()));
+        }
+    }
+    lines
+}
+
+fn message_lines(table: &Table) -> Vec<Line<'static>> {
+    if table.notes.is_empty() {
+        return vec![Line::from("No messages".dim())];
+    }
+    let mut lines = vec![];
+    for note in &table.notes {
+        let heading = Span::raw(note.heading.clone());
+        lines.push(Line::from(match note.kind {
+            NoteKind::Error => heading.red().bold(),
+            NoteKind::Invalid => heading.red().bold(),
+            NoteKind::Info => heading.bold(),
+        }));
+        for message in &note.messages {
+            lines.push(Line::from(format!("  {message}")));
+        }
+        lines.push(Line::default());
+    }
+    lines
+}
@@ -0,0 +1,180 @@
+use crate::{
+    Goal, Metric,
+    bench::Tags,
+    cli::CapacityArgs,
+    report::{Percentile, ReportArchive},
+    units::{Unit, format_duration},
+};
+use anyhow::Result;
+use std::time::Duration;
+
+/// The name of the [`Metric::Total`] that the capacity found by `--find-capacity` is stored as
+pub(crate) const CAPACITY_METRIC: &str = "Capacity";
+
+/// The rate the search starts from when `--operations-per-second` is not specified
+pub(crate) const DEFAULT_START_RATE: u64 = 1000;
+/// The search stops once the gap between the highest sustained rate and the lowest violating rate is within this fraction of the sustained rate
+const PRECISION: f64 = 0.05;
+const MAX_PROBES: usize = 16;
+/// A bench must achieve at least this fraction of the requested rate for the rate to be sustained
+const SUSTAINED_FRACTION: f64 = 0.95;
+
+/// The service level objective that a rate must meet to be sustained
+pub(crate) struct Slo {
+    p99: Duration,
+    error_rate: f64,
+}
+
+impl Slo {
+    /// Returns None if `--find-capacity` was not specified
+    pub(crate) fn from_args(args: &CapacityArgs) -> Option<Self> {
+        if args.find_capacity {
+            Some(Slo {
+                p99: args
+                    .slo_p99
+                    .expect("clap requires --slo-p99 for --find-capacity"),
+                error_rate: args.slo_error_rate.unwrap_or(0.0),
+            })
+        } else {
+            None
+        }
+    }
+
+    /// Returns the reason the results of a bench run at `rate` do not meet the SLO, or None if they do.
+    /// Pubsub benches are judged by their produced messages.
+    pub(crate) fn violation(&self, archive: &ReportArchive, rate: u64) -> Option<String> {
+        let (achieved, p99, errors, total) = if let Some(report) = &archive.operations_report {
+            (
+                report.total_operations_per_second as f64,
+                report.time_percentiles[Percentile::P99 as usize],
+                report.total_errors,
+                report.total,
+            )
+        } else if let Some(report) = &archive.pubsub_report {
+            (
+                report.produce_per_second as f64,
+                report.produce_time_percentiles[Percentile::P99 as usize],
+                report.total_produce_error,
+                report.total_produce,
+            )
+        } else {
+            return Some("the bench did not report any operations".to_owned());
+        };
+
+        let attempted = total + errors;
+        let error_rate = if attempted == 0 {
+            0.0
+        } else {
+            errors as f64 / attempted as f64
+        };
+        if achieved < rate as f64 * SUSTAINED_FRACTION {
+            Some(format!(
+                "only achieved {} ops/s",
+                Unit::Count.format(achieved)
+            ))
+        } else if p99 > self.p99 {
+            Some(format!(
+                "p99 of {} exceeds the SLO of {}",
+                format_duration(p99),
+                format_duration(self.p99)
+            ))
+        } else if error_rate > self.error_rate {
+            Some(format!(
+                "error rate of {} exceeds the SLO of {}",
+                Unit::Ratio.format(error_rate),
+                Unit::Ratio.format(self.error_rate)
+            ))
+        } else {
+            None
+        }
+    }
+}
+
+/// Searches for the highest sustained rate by doubling the rate until the SLO is violated and then bisecting
+pub(crate) struct CapacitySearch {
+    start: u64,
+    /// The highest rate that met the SLO
+    sustained: Option<u64>,
+    /// The lowest rate that violated the SLO
+    violated: Option<u64>,
+    /// Every rate probed, along with the reason it violated the SLO
+    probes: Vec<(u64, Option<String>)>,
+}
+
+impl CapacitySearch {
+    pub(crate) fn new(start: u64) -> Self {
+        CapacitySearch {
+            start: start.max(1),
+            sustained: None,
+            violated: None,
+            probes: vec![],
+        }
+    }
+
+    /// The next rate to probe, or None once the search is complete
+    pub(crate) fn next_rate(&self) -> Option<u64> {
+        if self.probes.len() >= MAX_PROBES {
+            return None;
+        }
+        match (self.sustained, self.violated) {
+            (None, None) => Some(self.start),
+            (Some(sustained), None) => Some(sustained.saturating_mul(2)),
+            (None, Some(1)) => None,
+            (None, Some(violated)) => Some(violated / 2),
+            (Some(sustained), Some(violated)) => {
+                let gap = violated - sustained;
+                if gap <= 1 || gap as f64 <= sustained as f64 * PRECISION {
+                    None
+                } else {
+                    Some(sustained + gap / 2)
+                }
+            }
+        }
+    }
+
+    pub(crate) fn record(&mut self, rate: u64, violation: Option<String>) {
+        match &violation {
+            None => self.sustained = self.sustained.max(Some(rate)),
+            Some(_) => self.violated = Some(self.violated.map_or(rate, |x| x.min(rate))),
+        }
+        self.probes.push((rate, violation));
+    }
+
+    /// Creates the results of the bench from the probe at the capacity, or from the lowest probe if no rate was sustained.
+    /// The capacity is added as a metric and every probe is listed in the info messages.
+    pub(crate) fn results(&self, tags: &Tags) -> Result<ReportArchive> {
+        let probe_rate = self
+            .sustained
+            .or(self.violated)
+            .expect("At least one rate is always probed");
+        let mut archive =
+            ReportArchive::load(&tags.with_operations_per_second(probe_rate).get_name())?;
+        archive.tags = tags.clone();
+
+        let capacity = self.sustained.unwrap_or(0);
+        archive.metrics.push(Metric::Total {
+            name: CAPACITY_METRIC.to_owned(),
+            value: capacity as f64,
+            unit: Unit::Count,
+            goal: Goal::BiggerIsBetter,
+        });
+        if self.sustained.is_none() {
+            archive.error_messages.push(format!(
+                "No probed rate met the SLO, the lowest rate probed was {} ops/s",
+                Unit::Count.format(probe_rate as f64)
+            ));
+        }
+        let mut probes = self.probes.clone();
+        probes.sort_by_key(|(rate, _)| *rate);
+        for (rate, violation) in probes {
+            let rate = Unit::Count.format(rate as f64);
+            archive.info_messages.push(match violation {
+                None => format!("Capacity probe at {rate} ops/s met the SLO"),
+                Some(violation) => {
+                    format!("Capacity probe at {rate} ops/s violated the SLO: {violation}")
+                }
+            });
+        }
+        Ok(archive)
+    }
+}
@@ -0,0 +1,487 @@
+use crate::{
+    Metric,
+    cli::LatencyChart,
+    filter::Filter,
+    report::{Percentile, Percentiles, ReportArchive},
+    tables::{ReportColumn, reports_in_last_run_matching, terminal_width},
+    units::Unit,
+};
+use anyhow::{Context, Result, anyhow};
+use console::{measure_text_width, pad_str, style};
+use std::time::Duration;
+use strum::IntoEnumIterator;
+
+const SPARKLINE_LEVELS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+/// The space reserved after each sparkline for its min/avg/max
+pub(crate) const STATS_WIDTH: usize = 48;
+pub(crate) const MIN_SPARKLINE_WIDTH: usize = 10;
+const HISTOGRAM_BUCKETS: usize = 12;
+const HISTOGRAM_BAR_WIDTH: usize = 40;
+const CDF_HEIGHT: usize = 11;
+const CDF_MAX_WIDTH: usize = 72;
+
+/// A series of values recorded each second that is displayed as a sparkline
+pub(crate) struct Series {
+    pub(crate) title: String,
+    current: Vec<f64>,
+    baseline: Option<Vec<f64>>,
+    unit: Unit,
+}
+
+/// The latency percentiles of a bench that are displayed as a distribution
+struct Distribution {
+    title: &'static str,
+    current: Percentiles,
+    baseline: Option<Percentiles>,
+}
+
+/// Displays charts of the per second series of every bench matching the filter, overlaid with the baseline.
+/// When `latency` is set the latency distribution of every bench is also displayed.
+pub fn charts(ignore_baseline: bool, filter: &str, latency: Option<LatencyChart>) -> Result<()> {
+    let filter = Filter::from_query(filter)
+        .with_context(|| format!("Failed to parse tag filter from {:?}", filter))?;
+    let names = reports_in_last_run_matching(&filter)?;
+    if names.is_empty() {
+        return Err(anyhow!("No benches in the last run match the filter"));
+    }
+
+    let terminal_width = terminal_width();
+    for name in names {
+        let column = if ignore_baseline {
+            ReportColumn::load(&name)?
+        } else {
+            ReportColumn::load_with_baseline(&name)?
+        };
+
+        println!("{}", style(&name).bold());
+        let series = series(&column);
+        if series.is_empty() {
+            println!("  No values were recorded each second");
+        }
+        let title_width = series
+            .iter()
+            .map(|x| measure_text_width(&x.title))
+            .max()
+            .unwrap_or(0)
+            .max("baseline".len());
+        let sparkline_width = terminal_width
+            .map(|x| x.saturating_sub(title_width + STATS_WIDTH + 4))
+            .unwrap_or(usize::MAX)
+            .max(MIN_SPARKLINE_WIDTH);
+        for series in &series {
+            render_series(series, title_width, sparkline_width);
+        }
+
+        if let Some(latency) = latency {
+            for distribution in distributions(&column) {
+                println!();
+                match latency {
+                    LatencyChart::Histogram => render_histogram(&distribution),
+                    LatencyChart::Cdf => render_cdf(&distribution, terminal_width),
+                }
+            }
+        }
+        println!();
+    }
+    Ok(())
+}
+
+pub(crate) fn series(column: &ReportColumn) -> Vec<Series> {
+    let mut series = vec![];
+    let mut push = |title: &str, unit: Unit, f: &dyn Fn(&ReportArchive) -> Option<Vec<f64>>| {
+        if let Some(current) = f(&column.current).filter(|x| !x.is_empty()) {
+            series.push(Series {
+                title: title.to_owned(),
+                current,
+                baseline: column.baseline.as_ref().and_then(f),
+                unit,
+            });
+        }
+    };
+
+    push("Operations", Unit::Count, &|archive| {
+        let report = archive.operations_report.as_ref()?;
+        Some(report.total_each_second.iter().map(|x| *x as f64).collect())
+    });
+    push("Target operations", Unit::Count, &|archive| {
+        let report = archive.operations_report.as_ref()?;
+        Some(
+            report
+                .requested_each_second
+                .iter()
+                .map(|x| *x as f64)
+                .collect(),
+        )
+    });
+    push("Errors", Unit::Count, &|archive| {
+        let report = archive.operations_report.as_ref()?;
+        Some(
+            report
+                .errors_each_second
+                .iter()
+                .map(|x| *x as f64)
+                .collect(),
+        )
+    });
+    for percentile in [Percentile::P50, Percentile::P99] {
+        push(
+            &format!("Latency p{}", percentile.name().trim()),
+            Unit::Seconds,
+            &|archive| {
+                let report = archive.operations_report.as_ref()?;
+                Some(percentile_each_second(
+                    &report.time_percentiles_each_second,
+                    percentile,
+                ))
+            },
+        );
+    }
+
+    push("Produce", Unit::Count, &|archive| {
+        let report = archive.pubsub_report.as_ref()?;
+        Some(
+            report
+                .produce_each_second
+                .iter()
+                .map(|x| *x as f64)
+                .collect(),
+        )
+    });
+    push("Target produce", Unit::Count, &|archive| {
+        let report = archive.pubsub_report.as_ref()?;
+        Some(
+            report
+                .requested_produce_each_second
+                .iter()
+                .map(|x| *x as f64)
+                .collect(),
+        )
+    });
+    push("Consume", Unit::Count, &|archive| {
+        let report = archive.pubsub_report.as_ref()?;
+        Some(
+            report
+                .consume_each_second
+                .iter()
+                .map(|x| *x as f64)
+                .collect(),
+        )
+    });
+    push("Backlog", Unit::Count, &|archive| {
+        let report = archive.pubsub_report.as_ref()?;
+        Some(
+            report
+                .backlog_each_second
+                .iter()
+                .map(|x| *x as f64)
+                .collect(),
+        )
+    });
+    push("Produce Latency p99", Unit::Seconds, &|archive| {
+        let report = archive.pubsub_report.as_ref()?;
+        Some(percentile_each_second(
+            &report.produce_time_percentiles_each_second,
+            Percentile::P99,
+        ))
+    });
+
+    for metric in &column.current.metrics {
+        if let Metric::EachSecond { name, unit, .. } = metric {
+            push(name, *unit, &|archive| {
+                archive.metrics.iter().find_map(|x| match x {
+                    Metric::EachSecond {
+                        name: other,
+                        values,
+                        ..
+                    } if other == name => Some(values.clone()),
+                    _ => None,
+                })
+            });
+        }
+    }
+    series
+}
+
+fn percentile_each_second(each_second: &[Percentiles], percentile: Percentile) -> Vec<f64> {
+    each_second
+        .iter()
+        .map(|x| x[percentile as usize].as_secs_f64())
+        .collect()
+}
+
+fn distributions(column: &ReportColumn) -> Vec<Distribution> {
+    let mut distributions = vec![];
+    let mut push = |title: &'static str, f: &dyn Fn(&ReportArchive) -> Option<Percentiles>| {
+        // All percentiles are zero when nothing was recorded
+        if let Some(current) = f(&column.current).filter(|x| x.iter().any(|x| !x.is_zero())) {
+            distributions.push(Distribution {
+                title,
+                current,
+                baseline: column
+                    .baseline
+                    .as_ref()
+                    .and_then(f)
+                    .filter(|x| x.iter().any(|x| !x.is_zero())),
+            });
+        }
+    };
+    push("Operation Latency", &|archive| {
+        Some(archive.operations_report.as_ref()?.time_percentiles)
+    });
+    push("Produce Latency", &|archive| {
+        Some(archive.pubsub_report.as_ref()?.produce_time_percentiles)
+    });
+    push("Consume Latency", &|archive| {
+        Some(archive.pubsub_report.as_ref()?.consume_time_percentiles)
+    });
+    distributions
+}
+
+fn render_series(series: &Series, title_width: usize, sparkline_width: usize) {
+    let (current, baseline) = series.lines(title_width, sparkline_width);
+    println!("{current}");
+    if let Some(baseline) = baseline {
+        println!("{}", style(baseline).dim());
+    }
+}
+
+impl Series {
+    /// The unstyled lines displaying the current values and, if present, the baseline values.
+    /// Both sparklines share the same scale so that they can be compared.
+    pub(crate) fn lines(
+        &self,
+        title_width: usize,
+        sparkline_width: usize,
+    ) -> (String, Option<String>) {
+        let values = self.current.iter().chain(self.baseline.iter().flatten());
+        let (min, max) = values.fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), x| {
+            (min.min(*x), max.max(*x))
+        });
+
+        let line = |title: &str, values: &[f64]| {
+            let sparkline = sparkline(&downsample(values, sparkline_width), min, max);
+            let count = values.len() as f64;
+            format!(
+                "  {} {}  min {}  avg {}  max {}",
+                pad_str(title, title_width, console::Alignment::Right, None),
+                pad_str(
+                    &sparkline,
+                    sparkline_width.min(self.current.len()),
+                    console::Alignment::Left,
+                    None
+                ),
+                self.unit
+                    .format(values.iter().copied().fold(f64::INFINITY, f64::min)),
+                self.unit.format(values.iter().sum::<f64>() / count),
+                self.unit
+                    .format(values.iter().copied().fold(f64::NEG_INFINITY, f64::max)),
+            )
+        };
+        (
+            line(&self.title, &self.current),
+            self.baseline
+                .as_ref()
+                .filter(|x| !x.is_empty())
+                .map(|x| line("baseline", x)),
+        )
+    }
+}
+
+/// Averages the values into at most `width` buckets so that the sparkline fits in the terminal
+fn downsample(values: &[f64], width: usize) -> Vec<f64> {
+    if values.len() <= width {
+        return values.to_vec();
+    }
+    let per_bucket = values.len().div_ceil(width);
+    values
+        .chunks(per_bucket)
+        .map(|x| x.iter().sum::<f64>() / x.len() as f64)
+        .collect()
+}
+
+fn sparkline(values: &[f64], min: f64, max: f64) -> String {
+    values
+        .iter()
+        .map(|value| {
+            let level = if max > min {
+                ((value - min) / (max - min) * (SPARKLINE_LEVELS.len() - 1) as f64).round() as usize
+            } else {
+                SPARKLINE_LEVELS.len() / 2
+            };
+            SPARKLINE_LEVELS[level.min(SPARKLINE_LEVELS.len() - 1)]
+        })
+        .collect()
+}
+
+/// The fraction of requests that completed within `x` seconds, interpolated between the recorded percentiles
+fn cdf(percentiles: &Percentiles, x: f64) -> f64 {
+    let points: Vec<(f64, f64)> = Percentile::iter()
+        .map(|p| (percentiles[p as usize].as_secs_f64(), p.value()))
+        .collect();
+    if x < points[0].0 {
+        return 0.0;
+    }
+    for pair in points.windows(2) {
+        let ((v0, q0), (v1, q1)) = (pair[0], pair[1]);
+        if x < v1 {
+            return q0 + (q1 - q0) * (x - v0) / (v1 - v0);
+        }
+    }
+    1.0
+}
+
+/// The range of latencies to chart, in seconds.
+/// The range is always positive so that it can be displayed on a log scale.
+fn latency_range(distribution: &Distribution) -> (f64, f64) {
+    let all = distribution
+        .current
+        .iter()
+        .chain(distribution.baseline.iter().flatten());
+    let min = all
+        .clone()
+        .filter(|x| !x.is_zero())
+        .min()
+        .copied()
+        .unwrap_or(Duration::from_nanos(1))
+        .as_secs_f64();
+    let max = all.max().unwrap().as_secs_f64();
+    if max > min {
+        (min, max)
+    } else {
+        (min, min * 2.0)
+    }
+}
+
+/// Draws a histogram approximated from the recorded percentiles, with log spaced buckets
+fn render_histogram(distribution: &Distribution) {
+    let (min, max) = latency_range(distribution);
+    let bucket_bound = |i: usize| min * (max / min).powf(i as f64 / HISTOGRAM_BUCKETS as f64);
+    let masses = |percentiles: &Percentiles| -> Vec<f64> {
+        (0..HISTOGRAM_BUCKETS)
+            .map(|i| {
+                let upper = if i + 1 == HISTOGRAM_BUCKETS {
+                    1.0
+                } else {
+                    cdf(percentiles, bucket_bound(i + 1))
+                };
+                let lower = if i == 0 {
+                    0.0
+                } else {
+                    cdf(percentiles, bucket_bound(i))
+                };
+                upper - lower
+            })
+            .collect()
+    };
+    let current = masses(&distribution.current);
+    let baseline = distribution.baseline.as_ref().map(masses);
+    let largest = current
+        .iter()
+        .chain(baseline.iter().flatten())
+        .copied()
+        .fold(0.0, f64::max);
+
+    let labels: Vec<String> = (0..HISTOGRAM_BUCKETS)
+        .map(|i| {
+            format!(
+                "{} - {}",
+                Unit::Seconds.format(bucket_bound(i)),
+                Unit::Seconds.format(bucket_bound(i + 1))
+            )
+        })
+        .collect();
+    let label_width = labels.iter().map(|x| measure_text_width(x)).max().unwrap();
+
+    println!("  {} (histogram)", style(distribution.title).bold());
+    for (i, label) in labels.iter().enumerate() {
+        let bar = |mass: f64, c: &str| {
+            let length = (mass / largest * HISTOGRAM_BAR_WIDTH as f64).round() as usize;
+            format!(
+                "{} {:>6.2}%",
+                pad_str(
+                    &c.repeat(length),
+                    HISTOGRAM_BAR_WIDTH,
+                    console::Alignment::Left,
+                    None
+                ),
+                mass * 100.0
+            )
+        };
+        println!(
+            "    {} {}",
+            pad_str(label, label_width, console::Alignment::Right, None),
+            bar(current[i], "█")
+        );
+        if let Some(baseline) = &baseline {
+            println!(
+                "    {} {}",
+                " ".repeat(label_width),
+                style(bar(baseline[i], "░")).dim()
+            );
+        }
+    }
+    if baseline.is_some() {
+        println!("    █ current  {}", style("░ baseline").dim());
+    }
+}
+
+/// Draws the cumulative distribution of latencies with a log scale x axis
+fn render_cdf(distribution: &Distribution, terminal_width: Option<usize>) {
+    const AXIS_WIDTH: usize = 7;
+    let (min, max) = latency_range(distribution);
+    let width = terminal_width
+        .map(|x| x.saturating_sub(AXIS_WIDTH + 4))
+        .unwrap_or(usize::MAX)
+        .clamp(MIN_SPARKLINE_WIDTH, CDF_MAX_WIDTH);
+
+    let plot = |percentiles: &Percentiles| -> Vec<usize> {
+        (0..width)
+            .map(|column| {
+                let x = min * (max / min).powf((column as f64 + 0.5) / width as f64);
+                (cdf(percentiles, x) * (CDF_HEIGHT - 1) as f64).round() as usize
+            })
+            .collect()
+    };
+    let current = plot(&distribution.current);
+    let baseline = distribution.baseline.as_ref().map(plot);
+
+    println!("  {} (cdf)", style(distribution.title).bold());
+    for row in (0..CDF_HEIGHT).rev() {
+        let label = match row {
+            0 => "0%",
+            row if row == CDF_HEIGHT - 1 => "100%",
+            row if row == (CDF_HEIGHT - 1) / 2 => "50%",
+            _ => "",
+        };
+        let mut line = String::new();
+        for column in 0..width {
+            if current[column] == row {
+                line.push('•');
+            } else if baseline.as_ref().is_some_and(|x| x[column] == row) {
+                line.push_str(&style('·').dim().to_string());
+            } else {
+                line.push(' ');
+            }
+        }
+        println!("  {label:>AXIS_WIDTH$} │{line}");
+    }
+    println!("  {:>AXIS_WIDTH$} └{}", "", "─".repeat(width));
+
+    let min_label = Unit::Seconds.format(min);
+    let max_label = Unit::Seconds.format(max);
+    let scale_label = "(log scale)";
+    let gap = width.saturating_sub(min_label.len() + max_label.len() + scale_label.len()) / 2;
+    println!(
+        "  {:>AXIS_WIDTH$}  {min_label}{}{scale_label}{}{max_label}",
+        "",
+        " ".repeat(gap),
+        " ".repeat(gap)
+    );
+    if baseline.is_some() {
+        println!(
+            "  {:>AXIS_WIDTH$}  • current  {}",
+            "",
+            style("· baseline").dim()
+        );
+    }
+}
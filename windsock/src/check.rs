@@ -0,0 +1,423 @@
+use crate::{Goal, bench::Tags, filter::Filter, report::ReportArchive, statistic::Statistic};
+use anyhow::{Context, Result, anyhow};
+use console::style;
+use serde::Deserialize;
+use std::{collections::BTreeMap, fmt::Display, path::Path, str::FromStr};
+
+/// The contents of a tolerances file, e.g.
+/// ```toml
+/// [tolerances]
+/// ops = "-5%"
+/// p99 = "+10%"
+/// errors = "<= 0"
+/// ```
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TolerancesFile {
+    tolerances: BTreeMap<String, String>,
+}
+
+/// The allowed change of each statistic when comparing a bench against its baseline
+pub(crate) struct Tolerances(Vec<(Statistic, Tolerance)>);
+
+impl Tolerances {
+    pub(crate) fn load(path: &Path) -> Result<Self> {
+        let contents = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read tolerances file {path:?}"))?;
+        let file: TolerancesFile = toml::from_str(&contents)
+            .with_context(|| format!("Failed to parse tolerances file {path:?}"))?;
+        Self::from_map(&file.tolerances)
+            .with_context(|| format!("Invalid tolerance in tolerances file {path:?}"))
+    }
+
+    pub(crate) fn from_map(map: &BTreeMap<String, String>) -> Result<Self> {
+        map.iter()
+            .map(|(statistic, tolerance)| {
+                Ok((
+                    statistic.parse()?,
+                    tolerance
+                        .parse()
+                        .with_context(|| format!("Invalid tolerance for {statistic:?}"))?,
+                ))
+            })
+            .collect::<Result<_>>()
+            .map(Tolerances)
+    }
+
+    /// Returns true if any tolerance can be checked without a baseline
+    fn has_absolute(&self) -> bool {
+        self.0
+            .iter()
+            .any(|(_, x)| matches!(x, Tolerance::AtMost(_) | Tolerance::AtLeast(_)))
+    }
+
+    /// Checks every tolerance against the bench.
+    /// Relative tolerances are skipped when there is no baseline.
+    pub(crate) fn check(
+        &self,
+        name: String,
+        current: ReportArchive,
+        baseline: Option<&ReportArchive>,
+    ) -> BenchCheck {
+        let mut violations = vec![];
+        let mut unchecked = vec![];
+        for (statistic, tolerance) in &self.0 {
+            let Some(measured) = statistic.measure(&current) else {
+                unchecked.push(format!(
+                    "{statistic} is not reported by the bench, so tolerance {tolerance} was not checked"
+                ));
+                continue;
+            };
+            let baseline = baseline.and_then(|x| statistic.measure(x));
+            let violated = match (tolerance, &baseline) {
+                (Tolerance::MaxDecrease(percent) | Tolerance::MaxIncrease(percent), Some(base)) => {
+                    if tolerance.increase_is_worse(measured.goal) {
+                        measured.value > base.value * (1.0 + percent / 100.0)
+                    } else {
+                        measured.value < base.value * (1.0 - percent / 100.0)
+                    }
+                }
+                (Tolerance::MaxDecrease(_) | Tolerance::MaxIncrease(_), None) => false,
+                (Tolerance::AtMost(limit), _) => measured.value > *limit,
+                (Tolerance::AtLeast(limit), _) => measured.value < *limit,
+            };
+            if violated {
+                violations.push(match baseline {
+                    Some(base) => format!(
+                        "{statistic}: {} -> {} ({}) violates tolerance {tolerance}",
+                        base.display,
+                        measured.display,
+                        percent_change(base.value, measured.value)
+                    ),
+                    None => format!(
+                        "{statistic}: {} violates tolerance {tolerance}",
+                        measured.display
+                    ),
+                });
+            }
+        }
+        BenchCheck {
+            name,
+            archive: current,
+            has_baseline: baseline.is_some(),
+            violations,
+            unchecked,
+        }
+    }
+}
+
+/// Describes the change from `base` to `value` as a percentage of `base`
+fn percent_change(base: f64, value: f64) -> String {
+    if base == 0.0 {
+        if value == 0.0 {
+            "unchanged".to_owned()
+        } else {
+            "previously 0".to_owned()
+        }
+    } else {
+        format!("{:+.1}%", (value - base) / base * 100.0)
+    }
+}
+
+/// How much a statistic may change before it is considered a regression.
+/// A percentage may only be exceeded in the direction that is worse for the statistic, improvements never violate it.
+/// So `p99 = "-5%"` and `p99 = "+5%"` both allow p99 latency to increase by 5%.
+/// The sign only determines the direction for statistics that are neither better when bigger nor when smaller.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum Tolerance {
+    /// e.g. "-5%" the value may decrease by at most 5% from the baseline
+    MaxDecrease(f64),
+    /// e.g. "+10%" the value may increase by at most 10% from the baseline
+    MaxIncrease(f64),
+    /// e.g. "<= 0" the value must never be greater than 0
+    AtMost(f64),
+    /// e.g. ">= 1000" the value must never be less than 1000
+    AtLeast(f64),
+}
+
+impl Tolerance {
+    /// Returns true if the percentage tolerance limits how far the statistic may increase, rather than decrease
+    fn increase_is_worse(&self, goal: Goal) -> bool {
+        match goal {
+            Goal::BiggerIsBetter => false,
+            Goal::SmallerIsBetter => true,
+            Goal::None => matches!(self, Tolerance::MaxIncrease(_)),
+        }
+    }
+}
+
+impl FromStr for Tolerance {
+    type Err = anyhow::Error;
+
+    fn from_str(text: &str) -> Result<Self> {
+        let text = text.trim();
+        let parse_number = |number: &str| {
+            number.trim().parse::<f64>().map_err(|_| {
+                anyhow!(
+                    "Expected a tolerance like \"-5%\", \"+10%\", \"<= 0\" or \">= 1000\" but was {text:?}"
+                )
+            })
+        };
+        if let Some(limit) = text.strip_prefix("<=") {
+            Ok(Tolerance::AtMost(parse_number(limit)?))
+        } else if let Some(limit) = text.strip_prefix(">=") {
+            Ok(Tolerance::AtLeast(parse_number(limit)?))
+        } else if let Some(percent) = text.strip_suffix('%') {
+            if let Some(percent) = percent.strip_prefix('-') {
+                Ok(Tolerance::MaxDecrease(parse_number(percent)?))
+            } else if let Some(percent) = percent.strip_prefix('+') {
+                Ok(Tolerance::MaxIncrease(parse_number(percent)?))
+            } else {
+                Err(anyhow!(
+                    "A percentage tolerance must start with '-' or '+' to specify the direction of the allowed change, but was {text:?}"
+                ))
+            }
+        } else {
+            parse_number(text)?;
+            Err(anyhow!(
+                "A tolerance that is just a number is ambiguous, use \"<= {text}\" or \">= {text}\" instead"
+            ))
+        }
+    }
+}
+
+impl Display for Tolerance {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Tolerance::MaxDecrease(percent) => write!(f, "-{percent}%"),
+            Tolerance::MaxIncrease(percent) => write!(f, "+{percent}%"),
+            Tolerance::AtMost(limit) => write!(f, "<= {limit}"),
+            Tolerance::AtLeast(limit) => write!(f, ">= {limit}"),
+        }
+    }
+}
+
+/// The result of checking a single bench
+pub(crate) struct BenchCheck {
+    pub(crate) name: String,
+    /// The results of the bench in the last run
+    pub(crate) archive: ReportArchive,
+    pub(crate) has_baseline: bool,
+    pub(crate) violations: Vec<String>,
+    /// The tolerances that could not be checked because the bench does not report their statistic
+    pub(crate) unchecked: Vec<String>,
+}
+
+/// Checks every bench in the last run matching the filter against its baseline
+pub(crate) fn check_last_run(filter: &str, tolerances: &Tolerances) -> Result<Vec<BenchCheck>> {
+    let filter = Filter::from_query(filter)
+        .with_context(|| format!("Failed to parse tag filter from {:?}", filter))?;
+
+    let mut checks = vec![];
+    for name in ReportArchive::reports_in_last_run() {
+        if !filter.matches(&Tags::from_name(&name)?) {
+            continue;
+        }
+        let current = ReportArchive::load(&name)?;
+        let baseline = ReportArchive::load_baseline(&name)?;
+        checks.push(tolerances.check(name, current, baseline.as_ref()));
+    }
+    Ok(checks)
+}
+
+/// Compares the last run against the baseline, returning an error if any bench regressed beyond the tolerances
+pub fn check(filter: &str, tolerances: &Tolerances, junit: Option<&Path>) -> Result<()> {
+    let checks = check_last_run(filter, tolerances)?;
+
+    if checks.is_empty() {
+        return Err(anyhow!(
+            "No benches in the last run matched the specified filter"
+        ));
+    }
+    if !checks.iter().any(|x| x.has_baseline) && !tolerances.has_absolute() {
+        return Err(anyhow!(
+            "None of the benches have a baseline to compare against and there are no absolute tolerances such as \"<= 0\", use `baseline-set` to set a baseline"
+        ));
+    }
+
+    for check in &checks {
+        if !check.has_baseline {
+            println!(
+                "{}",
+                style(format!(
+                    "No baseline for {}, only absolute tolerances were checked",
+                    check.name
+                ))
+                .yellow()
+            );
+        }
+        for unchecked in &check.unchecked {
+            println!("{}", style(format!("{}: {unchecked}", check.name)).yellow());
+        }
+    }
+
+    if let Some(path) = junit {
+        crate::junit::write_check_report(path, &checks)?;
+    }
+
+    let regressed: Vec<&BenchCheck> = checks.iter().filter(|x| !x.violations.is_empty()).collect();
+    for check in &regressed {
+        println!("{}", style(&check.name).red().bold());
+        for violation in &check.violations {
+            println!("    {violation}");
+        }
+    }
+
+    if regressed.is_empty() {
+        println!(
+            "{}",
+            style(format!(
+                "All {} benches are within tolerances",
+                checks.len()
+            ))
+            .green()
+            .bold()
+        );
+        Ok(())
+    } else {
+        Err(anyhow!(
+            "{} of {} benches violated their tolerances",
+            regressed.len(),
+            checks.len()
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::report::{OperationsReport, PubSubReport};
+    use std::collections::HashMap;
+    use time::OffsetDateTime;
+
+    fn archive(ops: u32, errors: u64) -> ReportArchive {
+        ReportArchive {
+            running_in_release: true,
+            bencher_cpus: None,
+            tags: Tags(HashMap::new()),
+            bench_started_at: OffsetDateTime::UNIX_EPOCH,
+            operations_report: Some(OperationsReport {
+                total_operations_per_second: ops,
+                total: ops as u64,
+                total_errors: errors,
+                ..Default::default()
+            }),
+            pubsub_report: None,
+            metrics: vec![],
+            error_messages: vec![],
+            info_messages: vec![],
+        }
+    }
+
+    fn with_p99(mut archive: ReportArchive, millis: u64) -> ReportArchive {
+        let report = archive.operations_report.as_mut().unwrap();
+        report.time_percentiles[crate::report::Percentile::P99 as usize] =
+            std::time::Duration::from_millis(millis);
+        archive
+    }
+
+    fn tolerances(pairs: &[(&str, &str)]) -> Tolerances {
+        Tolerances::from_map(
+            &pairs
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect(),
+        )
+        .unwrap()
+    }
+
+    fn violations(
+        tolerances: &Tolerances,
+        current: &ReportArchive,
+        baseline: Option<&ReportArchive>,
+    ) -> Vec<String> {
+        tolerances
+            .check("bench".to_owned(), current.clone(), baseline)
+            .violations
+    }
+
+    #[test]
+    fn parse_round_trip() {
+        for (text, tolerance) in [
+            ("-5%", Tolerance::MaxDecrease(5.0)),
+            ("+10%", Tolerance::MaxIncrease(10.0)),
+            ("+2.5%", Tolerance::MaxIncrease(2.5)),
+            ("<= 0", Tolerance::AtMost(0.0)),
+            (">= 1000", Tolerance::AtLeast(1000.0)),
+        ] {
+            let parsed: Tolerance = text.parse().unwrap();
+            assert_eq!(parsed, tolerance);
+            assert_eq!(parsed.to_string().parse::<Tolerance>().unwrap(), tolerance);
+        }
+        assert_eq!(
+            " <=5 ".parse::<Tolerance>().unwrap(),
+            Tolerance::AtMost(5.0)
+        );
+    }
+
+    #[test]
+    fn parse_errors() {
+        for text in ["5%", "5", "-x%", "<= many", "", "%"] {
+            assert!(text.parse::<Tolerance>().is_err(), "{text} parsed");
+        }
+    }
+
+    #[test]
+    fn percentages_follow_goal() {
+        let base = with_p99(archive(1000, 0), 10);
+
+        // ops is bigger is better, so only a decrease can violate
+        for ops_tolerance in ["-5%", "+5%"] {
+            let tolerances = tolerances(&[("ops", ops_tolerance)]);
+            assert!(violations(&tolerances, &archive(2000, 0), Some(&base)).is_empty());
+            assert!(violations(&tolerances, &archive(960, 0), Some(&base)).is_empty());
+            assert_eq!(
+                violations(&tolerances, &archive(900, 0), Some(&base)).len(),
+                1
+            );
+        }
+
+        // p99 is smaller is better, so only an increase can violate
+        for p99_tolerance in ["-5%", "+5%"] {
+            let tolerances = tolerances(&[("p99", p99_tolerance)]);
+            let improved = with_p99(archive(1000, 0), 5);
+            assert!(violations(&tolerances, &improved, Some(&base)).is_empty());
+            let regressed = with_p99(archive(1000, 0), 11);
+            assert_eq!(violations(&tolerances, &regressed, Some(&base)).len(), 1);
+        }
+    }
+
+    #[test]
+    fn increase_from_zero() {
+        let tolerances = tolerances(&[("errors", "+10%")]);
+        let violations = violations(&tolerances, &archive(1000, 3), Some(&archive(1000, 0)));
+        assert_eq!(violations.len(), 1);
+        assert!(!violations[0].contains("inf"), "{}", violations[0]);
+        assert!(violations[0].contains("previously 0"), "{}", violations[0]);
+    }
+
+    #[test]
+    fn absolute_without_baseline() {
+        let tolerances = tolerances(&[("errors", "<= 0"), ("ops", "-5%")]);
+        assert!(tolerances.has_absolute());
+        assert!(violations(&tolerances, &archive(1, 0), None).is_empty());
+        assert_eq!(violations(&tolerances, &archive(1, 1), None).len(), 1);
+        assert!(!self::tolerances(&[("ops", "-5%")]).has_absolute());
+    }
+
+    #[test]
+    fn pubsub_errors_are_checked() {
+        let tolerances = tolerances(&[("errors", "<= 0"), ("ops", ">= 1")]);
+        let mut pubsub = archive(0, 0);
+        pubsub.operations_report = None;
+        pubsub.pubsub_report = Some(PubSubReport {
+            total_produce: 10,
+            total_produce_error: 2,
+            ..Default::default()
+        });
+        let check = tolerances.check("bench".to_owned(), pubsub, None);
+        assert_eq!(check.violations.len(), 1);
+        // ops is not reported by pubsub benches, which is reported rather than silently skipped
+        assert_eq!(check.unchecked.len(), 1);
+    }
+}
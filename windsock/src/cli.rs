@@ -1,5 +1,19 @@
+use crate::affinity::CpuSet;
+use crate::filter::query_from_name;
+use crate::load_profile::LoadProfile;
+use crate::report::Percentile;
+use crate::statistic::{Statistic, parse_percentile};
+use crate::tables::SortKey;
+use crate::units::parse_duration;
 use anyhow::{Error, anyhow};
 use clap::{Args, Parser, Subcommand};
+use std::collections::BTreeSet;
+use std::fmt::Display;
+use std::num::NonZeroUsize;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::time::Duration;
+use strum::IntoEnumIterator;
 
 const ABOUT: &str = r#"Bench Names:
     Each benchmark has a unique name, this name is used by many options listed below.
@@ -8,15 +22,31 @@ const ABOUT: &str = r#"Bench Names:
 
 Tag Filters:
     Many options below take tag filters that specify which benches to include.
-    Tag filters specify which benches to include and the filter results are unioned.
+    A filter is a list of conditions separated by whitespace, a bench is included if it meets every condition.
 
     So:
     * The filter "foo=some_value" will include only benches with the tag key `foo` and the tag value `some_value`
     * The filter "foo=some_value bar=another_value" will include only benches that match "foo=some_value" and "bar=another_value"
     * The filter "" will include all benches
 
+    Conditions:
+    * foo=a|b       - the value of `foo` is `a` or `b`
+    * foo!=a        - the bench does not match foo=a, including benches without the tag key `foo`
+    * foo=cluster*  - the value of `foo` matches a glob, `*` matches any characters and `?` matches any single character
+    * foo=/^a.+b$/  - the value of `foo` matches a regex
+    * foo>=100      - the value of `foo` is a number satisfying the comparison, also supports >, < and <=
+    * foo           - the bench has the tag key `foo`
+    * !condition    - the bench does not meet the condition
+    * (conditions | conditions) - the bench meets either group of conditions
+
     A filters tags can also be separated by commas allowing names to function as filters.
-    So: foo=some_value,bar=another_value is a name but it can also be used where a filter is accepted."#;
+    So: foo=some_value,bar=another_value is a name but it can also be used where a filter is accepted.
+
+    Any `\`, `,`, `=` or `|` that is part of a tag key or value is escaped with a `\` in names and filters.
+    So the tag key `path` with the tag value `a=b` has the name path=a\=b
+    Any `*` or `?` that is part of a tag value, or a `/` at the start of a tag value, is also escaped with a `\` in names and filters, e.g. path=a\*b
+    Any `!`, `<` or `>` that is part of a tag key, or a `(` or `)` at the start of a tag key, is also escaped with a `\` in names and filters, e.g. a\!=b
+    A `/` at the start of a value is taken literally unless it is closed by another `/` followed by whitespace, `|` or `)`, e.g. path=/data or path=/tmp/x"#;
 
 #[derive(Subcommand, Clone)]
 pub enum Command {
@@ -62,8 +92,25 @@ pub enum Command {
     BaselineClear,
 
     /// Generate graphs webpage from the last benchmarks run.
+    /// The webpage is a single self contained html file written to windsock_data/webpage/index.html
+    ///
+    /// Usage: Optionally provide tag filters
     #[clap(verbatim_doc_comment)]
-    GenerateWebpage,
+    GenerateWebpage {
+        /// Do not compare against the set baseline.
+        #[clap(long, verbatim_doc_comment)]
+        ignore_baseline: bool,
+
+        /// Benches that differ only by these tag keys are displayed together in the same charts.
+        /// By default every bench is displayed in its own charts.
+        /// e.g. `--compare-tags topology,connection_count`
+        #[clap(long, verbatim_doc_comment, value_delimiter = ',')]
+        compare_tags: Vec<String>,
+
+        /// e.g. "db=kafka connection_count=100"
+        #[clap(verbatim_doc_comment)]
+        filter: Option<String>,
+    },
 
     /// Display results from the last benchmark run by:
     ///     Listing bench results matching tag filters.
@@ -76,6 +123,30 @@ pub enum Command {
         #[clap(long, verbatim_doc_comment)]
         ignore_baseline: bool,
 
+        /// Display a single row for each bench containing its key statistics, instead of the full results.
+        #[clap(long, verbatim_doc_comment)]
+        summary: bool,
+
+        /// Sort the rows of the summary by `name` or by any statistic accepted by `pivot --statistic`.
+        /// e.g. `--sort-by p99`
+        #[clap(
+            long,
+            verbatim_doc_comment,
+            requires = "summary",
+            default_value = "name"
+        )]
+        sort_by: SortKey,
+
+        /// Sort the rows of the summary in descending order.
+        #[clap(long, verbatim_doc_comment, requires = "summary")]
+        descending: bool,
+
+        #[clap(flatten)]
+        display: DisplayArgs,
+
+        #[clap(flatten)]
+        sections: SectionArgs,
+
         /// e.g. "db=kafka connection_count=100"
         #[clap(verbatim_doc_comment)]
         filter: Option<String>,
@@ -87,7 +158,15 @@ pub enum Command {
     /// Usage: First provide the base benchmark name then provide benchmark names to compare against the base.
     ///     "base_name other_name1 other_name2"
     #[clap(verbatim_doc_comment)]
-    CompareByName { filter: String },
+    CompareByName {
+        filter: String,
+
+        #[clap(flatten)]
+        display: DisplayArgs,
+
+        #[clap(flatten)]
+        sections: SectionArgs,
+    },
 
     /// Display results from the last benchmark run by:
     ///     Comparing benches matching tag filters against a specific base bench.
@@ -95,13 +174,213 @@ pub enum Command {
     /// Usage: First provide the base benchmark name then provide tag filters
     ///     "base_name db=kafka connection_count=10"
     #[clap(verbatim_doc_comment)]
-    CompareByTags { filter: String },
+    CompareByTags {
+        filter: String,
+
+        #[clap(flatten)]
+        display: DisplayArgs,
+
+        #[clap(flatten)]
+        sections: SectionArgs,
+    },
+
+    /// Display charts of the last benchmark run in the terminal:
+    ///     A sparkline of each series recorded every second, such as throughput, errors, backlog and latency.
+    ///     The baseline is displayed under each sparkline using the same scale.
+    ///
+    /// Usage: Optionally provide tag filters
+    #[clap(verbatim_doc_comment)]
+    Charts {
+        /// Do not compare against the set baseline.
+        #[clap(long, verbatim_doc_comment)]
+        ignore_baseline: bool,
+
+        /// Also display the latency distribution of each bench, approximated from its percentiles.
+        /// * histogram - the fraction of requests in each range of latencies
+        /// * cdf       - the fraction of requests completed within each latency
+        #[clap(long, verbatim_doc_comment, value_enum)]
+        latency: Option<LatencyChart>,
+
+        /// e.g. "db=kafka connection_count=100"
+        #[clap(verbatim_doc_comment)]
+        filter: Option<String>,
+    },
+
+    /// Display how latency changes with throughput for benches run with a sweep of `--operations-per-second`.
+    /// The runs of each bench at every rate are plotted as a curve of the achieved throughput against the latency, overlaid with the baseline.
+    ///
+    /// Usage: Optionally provide tag filters
+    #[clap(verbatim_doc_comment)]
+    Curves {
+        /// Do not compare against the set baseline.
+        #[clap(long, verbatim_doc_comment)]
+        ignore_baseline: bool,
+
+        /// The latency percentile to plot.
+        #[clap(long, verbatim_doc_comment, default_value = "p99", value_parser = parse_percentile)]
+        percentile: Percentile,
+
+        /// e.g. "db=kafka connection_count=100"
+        #[clap(verbatim_doc_comment)]
+        filter: Option<String>,
+    },
+
+    /// Browse the results of the last run and the baseline in an interactive terminal UI.
+    /// Benches can be filtered by tags, compared against the baseline and their per second charts and messages inspected.
+    ///
+    /// Usage: Optionally provide the initial tag filters
+    #[clap(verbatim_doc_comment)]
+    Browse {
+        /// e.g. "db=kafka connection_count=100"
+        #[clap(verbatim_doc_comment)]
+        filter: Option<String>,
+    },
+
+    /// Display results from the last benchmark run by:
+    ///     Displaying a single statistic in a grid, with rows and columns formed from the values of two tag keys.
+    ///     Each cell is compared against the baseline of its bench.
+    ///
+    /// Usage: Provide the tag keys for the rows and columns and optionally provide tag filters
+    ///     --rows connection_count --columns topology --statistic p99 "db=kafka"
+    #[clap(verbatim_doc_comment)]
+    Pivot {
+        /// The tag key whose values form the rows of the grid
+        #[clap(long, verbatim_doc_comment)]
+        rows: String,
+
+        /// The tag key whose values form the columns of the grid
+        #[clap(long, verbatim_doc_comment)]
+        columns: String,
+
+        /// The statistic to display in each cell.
+        /// Valid statistics are: ops, total, errors, error_rate, mean, min, max, p<percentile> (e.g. p50, p99, p99.9), produce, consume, backlog, metric:<name>.
+        /// Latency statistics can be prefixed with produce_ or consume_ to select pubsub latencies, e.g. produce_p99
+        #[clap(long, verbatim_doc_comment, default_value = "ops")]
+        statistic: Statistic,
+
+        /// Do not compare against the set baseline.
+        #[clap(long, verbatim_doc_comment)]
+        ignore_baseline: bool,
+
+        #[clap(flatten)]
+        display: DisplayArgs,
+
+        /// e.g. "db=kafka connection_count=100"
+        #[clap(verbatim_doc_comment)]
+        filter: Option<String>,
+    },
+
+    /// Compare the last benchmark run against the baseline and exit with a nonzero status if any bench regressed beyond the specified tolerances.
+    /// Intended for use in CI.
+    ///
+    /// Usage: Provide a tolerances file, unless they are configured in windsock.toml, and optionally provide tag filters
+    ///     --tolerances tolerances.toml "db=kafka"
+    #[clap(verbatim_doc_comment)]
+    Check {
+        /// A toml file specifying the allowed change of each statistic, e.g.
+        ///   [tolerances]
+        ///   ops = "-5%"      # operations per second may decrease by at most 5% from the baseline
+        ///   p99 = "+10%"     # p99 latency may increase by at most 10% from the baseline
+        ///   errors = "<= 0"  # there must be no errors
+        /// Any statistic accepted by `pivot --statistic` can be used.
+        /// Defaults to the [check.tolerances] table of windsock.toml.
+        #[clap(long, verbatim_doc_comment)]
+        tolerances: Option<PathBuf>,
+
+        /// Write a JUnit XML report of the check to the specified path, for display in CI.
+        /// Benches that violate their tolerances are marked as failures.
+        #[clap(long, verbatim_doc_comment)]
+        junit: Option<PathBuf>,
+
+        /// e.g. "db=kafka connection_count=100"
+        #[clap(verbatim_doc_comment)]
+        filter: Option<String>,
+    },
 
     /// Not for human use. Call this from your bench orchestration method to launch your bencher.
     #[clap(verbatim_doc_comment)]
     InternalRun(RunArgs),
 }
 
+impl Command {
+    /// Returns true if the command modifies the contents of `windsock_data`.
+    /// Only one windsock process may run these commands at a time.
+    pub fn modifies_windsock_data(&self) -> bool {
+        match self {
+            Command::CloudRun(args)
+            | Command::CloudSetupRunCleanup(args)
+            | Command::LocalRun(args) => !args.dry_run,
+            Command::CloudSetup { .. }
+            | Command::CloudCleanup
+            | Command::BaselineSet
+            | Command::BaselineClear
+            | Command::GenerateWebpage { .. } => true,
+            Command::List
+            | Command::Results { .. }
+            | Command::CompareByName { .. }
+            | Command::CompareByTags { .. }
+            | Command::Pivot { .. }
+            | Command::Charts { .. }
+            | Command::Curves { .. }
+            | Command::Browse { .. }
+            | Command::Check { .. }
+            // internal-run is launched by a windsock process that already holds the lock
+            | Command::InternalRun(_) => false,
+        }
+    }
+
+    /// The unconverted tag filter of the command, None if the command has no tag filter or it was not specified
+    pub fn tag_filter_mut(&mut self) -> Option<&mut String> {
+        match self {
+            Command::CloudRun(args)
+            | Command::CloudSetupRunCleanup(args)
+            | Command::LocalRun(args) => args.filter.as_mut(),
+            Command::CloudSetup { filter } => Some(filter),
+            Command::GenerateWebpage { filter, .. }
+            | Command::Results { filter, .. }
+            | Command::Charts { filter, .. }
+            | Command::Curves { filter, .. }
+            | Command::Browse { filter }
+            | Command::Pivot { filter, .. }
+            | Command::Check { filter, .. } => filter.as_mut(),
+            Command::List
+            | Command::CloudCleanup
+            | Command::BaselineSet
+            | Command::BaselineClear
+            | Command::CompareByName { .. }
+            | Command::CompareByTags { .. }
+            | Command::InternalRun(_) => None,
+        }
+    }
+
+    /// The tag filter the command selects benches with, converted from a name if necessary
+    pub fn tag_filter(&self) -> Option<String> {
+        match self {
+            Command::CloudRun(args)
+            | Command::CloudSetupRunCleanup(args)
+            | Command::LocalRun(args) => Some(args.filter()),
+            Command::CloudSetup { filter } => Some(query_from_name(filter)),
+            Command::GenerateWebpage { filter, .. }
+            | Command::Results { filter, .. }
+            | Command::Charts { filter, .. }
+            | Command::Curves { filter, .. }
+            | Command::Browse { filter }
+            | Command::Pivot { filter, .. }
+            | Command::Check { filter, .. } => {
+                Some(query_from_name(filter.as_deref().unwrap_or_default()))
+            }
+            // compare-by-* take bench names and internal-run is given a bench name by windsock itself
+            Command::List
+            | Command::CloudCleanup
+            | Command::BaselineSet
+            | Command::BaselineClear
+            | Command::CompareByName { .. }
+            | Command::CompareByTags { .. }
+            | Command::InternalRun(_) => None,
+        }
+    }
+}
+
 #[derive(Args, Clone)]
 pub struct RunArgs {
     /// Instruct benches to profile the application under test with the specified profilers.
@@ -114,10 +393,94 @@ pub struct RunArgs {
     #[clap(long, verbatim_doc_comment)]
     pub bench_length_seconds: Option<u32>,
 
+    /// How long in seconds to run each bench for before measuring begins, operations completed during the warmup are discarded.
+    /// By default benches have no warmup beyond the time taken to start their tasks.
+    #[clap(long, verbatim_doc_comment)]
+    pub warmup_seconds: Option<u32>,
+
     /// Instruct the benches to cap their operations per second to the specified amount.
     /// By default the benches will run with unlimited operations per second.
+    /// A list or range of rates runs every bench once at each rate, e.g. `1000,5000,10000` or `"1000..20000 step 1000"`
+    /// The results of each rate are tagged with `operations_per_second=<rate>`, use the `curves` command to view them.
+    #[clap(long, verbatim_doc_comment)]
+    pub operations_per_second: Option<OperationsPerSecond>,
+
+    /// Vary the target operations per second over the course of each bench instead of holding it constant.
+    /// Useful for testing autoscaling and backpressure.
+    /// The target rate of each second is recorded alongside the achieved rate.
+    /// * `ramp 1000..10000 over 60s` - increase linearly and then remain at the final rate
+    /// * `step 1000..10000 by 1000 every 10s` - increase in steps until the final rate is reached
+    /// * `sine 1000..10000 period 30s` - oscillate between the two rates
+    /// * `spike 1000..10000 at 20s for 5s` - run at the first rate except for a spike to the second rate
+    #[clap(long, verbatim_doc_comment, conflicts_with_all = ["operations_per_second", "find_capacity"])]
+    pub load_profile: Option<LoadProfile>,
+
+    /// Continue a previously interrupted run.
+    /// The results of the last run are kept and benches that already have valid results in the last run are skipped.
+    #[clap(long, verbatim_doc_comment, conflicts_with = "append")]
+    pub resume: bool,
+
+    /// Add the results of the benches run to the results of the last run instead of clearing the last run.
+    /// Benches that already have results in the last run will be rerun and their results replaced.
+    #[clap(long, verbatim_doc_comment)]
+    pub append: bool,
+
+    /// Continue running the remaining benches after a bench fails instead of skipping them.
+    /// A summary of every bench is printed at the end of the run and windsock exits nonzero if any bench failed.
+    #[clap(long, verbatim_doc_comment)]
+    pub keep_going: bool,
+
+    /// Fail a bench that takes longer than this many seconds to run, including the time taken to set it up and tear it down.
+    /// Timed out benches are reported as errors in the --junit report.
+    #[clap(long, verbatim_doc_comment)]
+    pub timeout_seconds: Option<u64>,
+
+    /// Write a JUnit XML report of the benches run to the specified path, for display in CI.
+    /// Benches that fail to run or that encounter errors are marked as failures.
     #[clap(long, verbatim_doc_comment)]
-    pub operations_per_second: Option<u64>,
+    pub junit: Option<PathBuf>,
+
+    /// Run each bench this many times, the results of each repetition are tagged with `repetition=<n>`.
+    /// Combine with `--order interleave` or `--order random` so that drift over the course of the run affects every bench equally.
+    #[clap(long, verbatim_doc_comment)]
+    pub repeat: Option<NonZeroUsize>,
+
+    /// Run benches in a different order to the order they are defined in, local-run only.
+    /// Cloud runs are always ordered to make the best use of the cloud resources.
+    /// * random - a random order, the seed is printed and recorded in the results so that the order can be reproduced with --seed
+    /// * interleave - run every bench once per repetition of --repeat, reversing the order of every second repetition.
+    ///   Compared benches alternate so that drift such as thermal throttling affects them equally.
+    #[clap(long, verbatim_doc_comment)]
+    pub order: Option<RunOrder>,
+
+    /// The seed used to shuffle the benches for `--order random`, a random seed is used if not specified.
+    #[clap(long, verbatim_doc_comment)]
+    pub seed: Option<u64>,
+
+    /// Pin the bencher to the specified CPU cores, e.g. `0-3,6`, linux only.
+    /// Takes precedence over any cores specified by the bench itself.
+    /// Use with --orchestrator-cpus to keep the bencher from competing with the database under test for the same cores.
+    /// The cores the bencher was pinned to are recorded in the results.
+    #[clap(long, verbatim_doc_comment)]
+    pub bencher_cpus: Option<CpuSet>,
+
+    /// Pin windsock and the benches orchestrating the run to the specified CPU cores, e.g. `7`, linux only.
+    /// Local databases started directly by the benches inherit this pinning unless they pin themselves, containers started through docker do not.
+    /// The bencher does not inherit this pinning, use --bencher-cpus to pin it.
+    #[clap(long, verbatim_doc_comment)]
+    pub orchestrator_cpus: Option<CpuSet>,
+
+    /// The repetition of --repeat that these args run, set by [`RunArgs::split_runs`]
+    #[clap(skip)]
+    pub repetition: Option<usize>,
+
+    #[clap(flatten)]
+    pub capacity: CapacityArgs,
+
+    /// Print the benches that would be run, in order, and the benches that would be skipped and why, then exit without running anything.
+    /// Cloud runs also display the resources each bench requires.
+    #[clap(long, verbatim_doc_comment)]
+    pub dry_run: bool,
 
     /// Run all benches that match the specified tag key/values.
     /// `tag_key=tag_value foo=bar`
@@ -129,11 +492,356 @@ impl RunArgs {
     pub fn filter(&self) -> String {
         match &self.filter {
             // convert a name into a filter by swapping commas for spaces
-            Some(filter) => filter.replace(',', " "),
+            Some(filter) => query_from_name(filter),
             // If not provided use the empty filter
             None => String::new(),
         }
     }
+
+    /// Returns an error if the combination of args cannot be run.
+    /// `cloud` is true for commands that run benches in the cloud.
+    pub fn validate(&self, cloud: bool) -> Result<(), Error> {
+        if cloud && self.order.is_some() {
+            return Err(anyhow!(
+                "--order is only supported by local-run, cloud runs are ordered to make the best use of the cloud resources"
+            ));
+        }
+        if self.seed.is_some() && self.order != Some(RunOrder::Random) {
+            return Err(anyhow!("--seed can only be used with --order random"));
+        }
+        if self.capacity.find_capacity && self.repeat.is_some_and(|x| x.get() > 1) {
+            return Err(anyhow!(
+                "--find-capacity already runs each bench many times and cannot be combined with --repeat"
+            ));
+        }
+        if self.capacity.find_capacity
+            && matches!(
+                self.operations_per_second,
+                Some(OperationsPerSecond::Sweep(_))
+            )
+        {
+            return Err(anyhow!(
+                "--find-capacity chooses the rates itself, --operations-per-second may only specify a single rate to start the search from"
+            ));
+        }
+        if let (Some(bencher), Some(orchestrator)) = (&self.bencher_cpus, &self.orchestrator_cpus) {
+            if bencher.overlaps(orchestrator) {
+                return Err(anyhow!(
+                    "--bencher-cpus {bencher} and --orchestrator-cpus {orchestrator} must not share any cores"
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Splits the args into one `RunArgs` for each run of a bench, a run for each rate of a sweep and each repetition of --repeat.
+    /// Other args are returned as is.
+    pub fn split_runs(&self) -> Vec<RunArgs> {
+        let rates = match &self.operations_per_second {
+            Some(OperationsPerSecond::Sweep(rates)) => rates
+                .iter()
+                .map(|rate| Some(OperationsPerSecond::Sweep(vec![*rate])))
+                .collect(),
+            operations_per_second => vec![operations_per_second.clone()],
+        };
+        let repetitions = match self.repeat {
+            Some(repeat) if repeat.get() > 1 => (1..=repeat.get()).map(Some).collect(),
+            _ => vec![None],
+        };
+        rates
+            .into_iter()
+            .flat_map(|operations_per_second| {
+                repetitions.iter().map(move |repetition| RunArgs {
+                    operations_per_second: operations_per_second.clone(),
+                    repetition: *repetition,
+                    ..self.clone()
+                })
+            })
+            .collect()
+    }
+
+    /// The rate to run the bench at, None for unlimited.
+    /// Sweeps must first be split by [`RunArgs::split_runs`].
+    pub fn rate(&self) -> Option<u64> {
+        match &self.operations_per_second {
+            Some(OperationsPerSecond::Rate(rate)) => Some(*rate),
+            Some(OperationsPerSecond::Sweep(rates)) => match rates.as_slice() {
+                [rate] => Some(*rate),
+                _ => panic!("Sweeps must be split into a run for each rate before running"),
+            },
+            None => None,
+        }
+    }
+
+    /// How the target rate changes over the course of the bench, either the `--load-profile` or a constant `--operations-per-second`.
+    /// Sweeps must first be split by [`RunArgs::split_runs`].
+    pub fn load_profile(&self) -> LoadProfile {
+        match &self.load_profile {
+            Some(profile) => profile.clone(),
+            None => self.rate().into(),
+        }
+    }
+
+    /// The rate to tag the results with when this run is one rate of a sweep split by [`RunArgs::split_runs`]
+    pub fn sweep_rate(&self) -> Option<u64> {
+        match &self.operations_per_second {
+            Some(OperationsPerSecond::Sweep(_)) => self.rate(),
+            _ => None,
+        }
+    }
+}
+
+/// The order that local-run runs benches in when `--order` is specified
+#[derive(clap::ValueEnum, Clone, Copy, PartialEq)]
+pub enum RunOrder {
+    Random,
+    Interleave,
+}
+
+impl Display for RunOrder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RunOrder::Random => write!(f, "random"),
+            RunOrder::Interleave => write!(f, "interleaved"),
+        }
+    }
+}
+
+/// Options for searching for the capacity of each bench
+#[derive(Args, Clone, Default)]
+pub struct CapacityArgs {
+    /// Instead of running each bench once, search for the highest operations per second it sustains within the SLO set by --slo-p99 and --slo-error-rate.
+    /// The bench is run repeatedly, doubling the rate until the SLO is violated and then bisecting between the highest sustained and lowest violating rates.
+    /// A rate is only sustained if the bench achieves at least 95% of it.
+    /// The search starts at --operations-per-second, or 1000 if not specified.
+    /// Each probe is stored tagged with `operations_per_second=<rate>` and the capacity found is stored in the results of the bench as the `capacity` statistic.
+    #[clap(long, verbatim_doc_comment, requires = "slo_p99")]
+    pub find_capacity: bool,
+
+    /// The p99 latency that a sustained rate must not exceed during --find-capacity, e.g. `10ms`
+    #[clap(long, verbatim_doc_comment, value_parser = parse_duration, requires = "find_capacity")]
+    pub slo_p99: Option<Duration>,
+
+    /// The percentage of operations that may error at a sustained rate during --find-capacity, e.g. `0.1%`.
+    /// By default no errors are allowed.
+    #[clap(long, verbatim_doc_comment, value_parser = parse_percentage, requires = "find_capacity")]
+    pub slo_error_rate: Option<f64>,
+}
+
+fn parse_percentage(text: &str) -> Result<f64, Error> {
+    text.trim()
+        .strip_suffix('%')
+        .and_then(|x| x.trim().parse::<f64>().ok())
+        .map(|x| x / 100.0)
+        .ok_or_else(|| {
+            anyhow!("Invalid percentage {text:?}, expected a number followed by %, e.g. 0.1%")
+        })
+}
+
+/// The value of `--operations-per-second`
+#[derive(Clone, Debug, PartialEq)]
+pub enum OperationsPerSecond {
+    /// Run each bench once at this rate
+    Rate(u64),
+    /// Run each bench once at each of these rates
+    Sweep(Vec<u64>),
+}
+
+/// The most rates a sweep may contain, as each rate is a separate run of every bench
+const MAX_SWEEP_RATES: u64 = 1000;
+
+impl FromStr for OperationsPerSecond {
+    type Err = Error;
+
+    /// Parses a single rate `1000`, a list of rates `1000,5000` or a range of rates `1000..20000 step 1000`.
+    /// Lists can contain ranges and the end of a range is included in the sweep.
+    /// The rates of a sweep are sorted and duplicates removed, so that each rate is run once.
+    fn from_str(text: &str) -> Result<Self, Error> {
+        if let Ok(rate) = text.trim().parse() {
+            return Ok(OperationsPerSecond::Rate(rate));
+        }
+
+        let parse_rate = |rate: &str| {
+            rate.parse::<u64>().map_err(|_| {
+                anyhow!("Invalid operations per second {rate:?}, expected a whole number")
+            })
+        };
+        let mut rates = BTreeSet::new();
+        for item in text.split(',').map(|x| x.trim()) {
+            match item.split_once("..") {
+                Some((start, rest)) => {
+                    let words: Vec<&str> = rest.split_whitespace().collect();
+                    let [end, "step", step] = words.as_slice() else {
+                        return Err(anyhow!(
+                            "Invalid range {item:?}, expected a range of the form `1000..20000 step 1000`"
+                        ));
+                    };
+                    let (start, end, step) = (
+                        parse_rate(start.trim())?,
+                        parse_rate(end)?,
+                        parse_rate(step)?,
+                    );
+                    if step == 0 || start > end {
+                        return Err(anyhow!(
+                            "Invalid range {item:?}, the step must be positive and the start must not be after the end"
+                        ));
+                    }
+                    if (end - start) / step >= MAX_SWEEP_RATES {
+                        return Err(anyhow!(
+                            "Invalid range {item:?}, it contains more than {MAX_SWEEP_RATES} rates, use a larger step"
+                        ));
+                    }
+                    rates.extend((start..=end).step_by(step as usize));
+                }
+                None => {
+                    rates.insert(parse_rate(item)?);
+                }
+            }
+            if rates.len() as u64 > MAX_SWEEP_RATES {
+                return Err(anyhow!(
+                    "Invalid operations per second {text:?}, it contains more than {MAX_SWEEP_RATES} rates"
+                ));
+            }
+        }
+        Ok(OperationsPerSecond::Sweep(rates.into_iter().collect()))
+    }
+}
+
+/// Options controlling how result tables are displayed
+#[derive(Args, Clone)]
+pub struct DisplayArgs {
+    /// The format to display the table in, defaults to terminal.
+    /// * terminal - colored table for reading in the terminal.
+    ///   Tables wider than the terminal are split into pages of columns, set COLUMNS to override the detected width.
+    /// * markdown - github flavoured markdown for pasting into PRs
+    /// * csv/tsv  - for importing into spreadsheets, values are unformatted with the unit of each row in its own column
+    #[clap(long, verbatim_doc_comment, value_enum)]
+    pub format: Option<TableFormat>,
+}
+
+impl DisplayArgs {
+    /// The format to display the table in
+    pub fn table_format(&self) -> TableFormat {
+        self.format.unwrap_or(TableFormat::Terminal)
+    }
+}
+
+#[derive(clap::ValueEnum, Clone, Copy)]
+pub enum TableFormat {
+    Terminal,
+    Markdown,
+    Csv,
+    Tsv,
+}
+
+/// How latency distributions are displayed by `charts`
+#[derive(clap::ValueEnum, Clone, Copy)]
+pub enum LatencyChart {
+    Histogram,
+    Cdf,
+}
+
+/// Options controlling which rows are included in result tables
+#[derive(Args, Clone, Default)]
+pub struct SectionArgs {
+    /// Only include these sections in the table.
+    /// Valid sections are: ops, pubsub, each-second, metrics, metric:<name>
+    /// e.g. `--include ops,metric:cpu_usage`
+    #[clap(long, verbatim_doc_comment, value_delimiter = ',')]
+    pub include: Vec<Section>,
+
+    /// Exclude these sections from the table, accepts the same sections as --include.
+    /// e.g. `--exclude each-second`
+    #[clap(long, verbatim_doc_comment, value_delimiter = ',')]
+    pub exclude: Vec<Section>,
+
+    /// Only include these percentiles in the table.
+    /// e.g. `--percentiles p50,p99,p99.9,max`
+    #[clap(long, verbatim_doc_comment, value_delimiter = ',', value_parser = parse_percentile)]
+    pub percentiles: Vec<Percentile>,
+
+    /// Summarise values recorded each second over windows of this many seconds.
+    /// e.g. `--each-second-window 10` displays one row for every 10 seconds of the bench
+    #[clap(long, verbatim_doc_comment)]
+    pub each_second_window: Option<NonZeroUsize>,
+
+    /// How each window of --each-second-window is summarised, defaults to avg.
+    #[clap(long, verbatim_doc_comment, value_enum)]
+    pub each_second_statistic: Option<WindowStatistic>,
+}
+
+impl SectionArgs {
+    /// Returns true if a section belonging to all of `sections` should be included in the table
+    pub fn shows(&self, sections: &[Section]) -> bool {
+        (self.include.is_empty() || sections.iter().any(|x| self.include.contains(x)))
+            && !sections.iter().any(|x| self.exclude.contains(x))
+    }
+
+    /// The percentiles to include in the table
+    pub fn percentiles(&self) -> impl Iterator<Item = Percentile> + '_ {
+        Percentile::iter().filter(|x| self.percentiles.is_empty() || self.percentiles.contains(x))
+    }
+}
+
+/// A group of rows in a result table that can be included or excluded
+#[derive(Clone, PartialEq, Debug)]
+pub enum Section {
+    Ops,
+    PubSub,
+    EachSecond,
+    Metrics,
+    Metric(String),
+}
+
+impl FromStr for Section {
+    type Err = Error;
+
+    fn from_str(text: &str) -> Result<Self, Error> {
+        match text {
+            "ops" => Ok(Section::Ops),
+            "pubsub" => Ok(Section::PubSub),
+            "each-second" => Ok(Section::EachSecond),
+            "metrics" => Ok(Section::Metrics),
+            text => match text.strip_prefix("metric:") {
+                Some(name) => Ok(Section::Metric(name.to_owned())),
+                None => Err(anyhow!(
+                    "Unknown section {text:?}, valid sections are: ops, pubsub, each-second, metrics, metric:<name>"
+                )),
+            },
+        }
+    }
+}
+
+#[derive(clap::ValueEnum, Clone, Copy, Default)]
+pub enum WindowStatistic {
+    Min,
+    #[default]
+    Avg,
+    Max,
+}
+
+impl WindowStatistic {
+    /// Summarises the values of a window, returns None if the window is empty
+    pub fn apply(&self, values: &[f64]) -> Option<f64> {
+        if values.is_empty() {
+            return None;
+        }
+        Some(match self {
+            WindowStatistic::Min => values.iter().copied().fold(f64::INFINITY, f64::min),
+            WindowStatistic::Avg => values.iter().sum::<f64>() / values.len() as f64,
+            WindowStatistic::Max => values.iter().copied().fold(f64::NEG_INFINITY, f64::max),
+        })
+    }
+}
+
+impl Display for WindowStatistic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            WindowStatistic::Min => write!(f, "min"),
+            WindowStatistic::Avg => write!(f, "avg"),
+            WindowStatistic::Max => write!(f, "max"),
+        }
+    }
 }
 
 #[derive(Parser)]
@@ -202,3 +910,64 @@ impl WindsockArgs {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(text: &str) -> OperationsPerSecond {
+        text.parse().unwrap()
+    }
+
+    #[test]
+    fn operations_per_second() {
+        assert_eq!(parse("1000"), OperationsPerSecond::Rate(1000));
+        assert_eq!(parse(" 1000 "), OperationsPerSecond::Rate(1000));
+        assert_eq!(
+            parse("5000, 1000"),
+            OperationsPerSecond::Sweep(vec![1000, 5000])
+        );
+        assert_eq!(
+            parse("1000..3000 step 1000"),
+            OperationsPerSecond::Sweep(vec![1000, 2000, 3000])
+        );
+        assert_eq!(
+            parse("1000..2500 step 1000,500"),
+            OperationsPerSecond::Sweep(vec![500, 1000, 2000])
+        );
+    }
+
+    #[test]
+    fn operations_per_second_deduplicated() {
+        assert_eq!(parse("1000,1000"), OperationsPerSecond::Sweep(vec![1000]));
+        assert_eq!(
+            parse("1000..3000 step 1000,2000..4000 step 1000"),
+            OperationsPerSecond::Sweep(vec![1000, 2000, 3000, 4000])
+        );
+    }
+
+    #[test]
+    fn operations_per_second_errors() {
+        for text in [
+            "",
+            "fast",
+            "-1000",
+            "1000,",
+            "1000..2000",
+            "1000..2000 by 100",
+            "1000..2000 step 0",
+            "2000..1000 step 100",
+            "1..1000000000 step 1",
+            "0..1000 step 1",
+        ] {
+            assert!(
+                text.parse::<OperationsPerSecond>().is_err(),
+                "{text} parsed"
+            );
+        }
+        // the limit applies across the whole list
+        let many: Vec<String> = (1..=1001).map(|x| x.to_string()).collect();
+        assert!(many.join(",").parse::<OperationsPerSecond>().is_err());
+        assert!(parse("1..1000 step 1") == OperationsPerSecond::Sweep((1..=1000).collect()));
+    }
+}
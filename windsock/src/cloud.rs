@@ -58,6 +58,15 @@ pub trait Cloud {
         benches
     }
 
+    /// Describes the resources required by a bench, e.g. the number and type of instances.
+    /// This is displayed alongside each bench by `--dry-run`, by default no description is displayed.
+    fn describe_resources(
+        &self,
+        _required_resources: &Self::CloudResourcesRequired,
+    ) -> Option<String> {
+        None
+    }
+
     /// This is called before running each bench.
     /// Use it to destroy or create resources as needed.
     /// However, this method will not be called when `--save-resources-file` or `--load-resources-file` is set.
@@ -0,0 +1,404 @@
+use crate::{
+    bench::Tags,
+    check::Tolerances,
+    cli::{DisplayArgs, OperationsPerSecond, RunArgs, SectionArgs, TableFormat, WindowStatistic},
+    filter::{Filter, query_from_name},
+    statistic::parse_percentile,
+};
+use anyhow::{Context, Result, anyhow};
+use clap::ValueEnum;
+use regex::{Captures, Regex};
+use serde::Deserialize;
+use std::{collections::BTreeMap, num::NonZeroUsize, path::PathBuf};
+
+/// Overrides the location of the config file
+const CONFIG_ENV_VAR: &str = "WINDSOCK_CONFIG";
+const CONFIG_FILE_NAME: &str = "windsock.toml";
+
+/// The contents of a `windsock.toml` file, e.g.
+/// ```toml
+/// [run]
+/// bench_length_seconds = 30
+/// warmup_seconds = 5
+/// profilers = ["flamegraph"]
+///
+/// [presets]
+/// quick = "topology=single connection_count=10"
+///
+/// [[overrides]]
+/// filter = "db=kafka"
+/// bench_length_seconds = 60
+///
+/// [display]
+/// format = "markdown"
+/// exclude = ["each-second"]
+///
+/// [check.tolerances]
+/// ops = "-5%"
+/// ```
+#[derive(Deserialize, Default)]
+#[serde(deny_unknown_fields)]
+struct ConfigFile {
+    #[serde(default)]
+    run: RunDefaults,
+    #[serde(default)]
+    presets: BTreeMap<String, String>,
+    #[serde(default)]
+    overrides: Vec<OverrideFile>,
+    #[serde(default)]
+    display: DisplayFile,
+    #[serde(default)]
+    check: CheckFile,
+}
+
+/// Defaults for the options of the commands that run benches
+#[derive(Deserialize, Default)]
+#[serde(deny_unknown_fields)]
+struct RunDefaults {
+    profilers: Option<Vec<String>>,
+    bench_length_seconds: Option<u32>,
+    warmup_seconds: Option<u32>,
+    operations_per_second: Option<u64>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct OverrideFile {
+    filter: String,
+    bench_length_seconds: Option<u32>,
+    warmup_seconds: Option<u32>,
+    operations_per_second: Option<u64>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(deny_unknown_fields)]
+struct DisplayFile {
+    format: Option<String>,
+    #[serde(default)]
+    include: Vec<String>,
+    #[serde(default)]
+    exclude: Vec<String>,
+    #[serde(default)]
+    percentiles: Vec<String>,
+    each_second_window: Option<NonZeroUsize>,
+    each_second_statistic: Option<String>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(deny_unknown_fields)]
+struct CheckFile {
+    tolerances: Option<BTreeMap<String, String>>,
+}
+
+/// Project wide defaults loaded from `windsock.toml`.
+/// Options specified on the command line always take precedence over the config.
+#[derive(Default)]
+pub(crate) struct Config {
+    path: Option<PathBuf>,
+    run: RunDefaults,
+    presets: BTreeMap<String, String>,
+    overrides: Vec<Override>,
+    format: Option<TableFormat>,
+    sections: SectionArgs,
+    tolerances: Option<Tolerances>,
+}
+
+/// Run options for the benches matching a filter
+struct Override {
+    filter: Filter,
+    bench_length_seconds: Option<u32>,
+    warmup_seconds: Option<u32>,
+    operations_per_second: Option<u64>,
+}
+
+impl Config {
+    /// Loads the config from the path in the `WINDSOCK_CONFIG` environment variable if set,
+    /// otherwise from `windsock.toml` in the current directory, if it exists.
+    /// Cargo runs benches from the directory of the crate containing them, so that is where the config is usually placed.
+    /// Under `cargo run` from another directory the crate directory in `CARGO_MANIFEST_DIR` is also checked.
+    pub(crate) fn load() -> Result<Self> {
+        let path = match std::env::var_os(CONFIG_ENV_VAR) {
+            Some(path) => PathBuf::from(path),
+            None => {
+                let mut candidates = std::iter::once(PathBuf::from(CONFIG_FILE_NAME)).chain(
+                    std::env::var_os("CARGO_MANIFEST_DIR")
+                        .map(|dir| PathBuf::from(dir).join(CONFIG_FILE_NAME)),
+                );
+                match candidates.find(|path| path.exists()) {
+                    Some(path) => path,
+                    None => return Ok(Config::default()),
+                }
+            }
+        };
+
+        let contents = std::fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read config file {path:?}"))?;
+        let file: ConfigFile = toml::from_str(&contents)
+            .with_context(|| format!("Failed to parse config file {path:?}"))?;
+        Self::from_file(file, path.clone()).with_context(|| format!("Invalid config file {path:?}"))
+    }
+
+    fn from_file(file: ConfigFile, path: PathBuf) -> Result<Self> {
+        let mut config = Config {
+            path: Some(path),
+            run: file.run,
+            presets: file.presets,
+            overrides: vec![],
+            format: None,
+            sections: SectionArgs::default(),
+            tolerances: None,
+        };
+        for (name, preset) in &config.presets {
+            let query = config.expand_presets_within(&query_from_name(preset), &mut vec![name])?;
+            Filter::from_query(&query)
+                .with_context(|| format!("Invalid filter for preset {name:?}"))?;
+        }
+        for x in file.overrides {
+            let query = config.expand_presets(&query_from_name(&x.filter))?;
+            config.overrides.push(Override {
+                filter: Filter::from_query(&query)
+                    .with_context(|| format!("Invalid filter for override {:?}", x.filter))?,
+                bench_length_seconds: x.bench_length_seconds,
+                warmup_seconds: x.warmup_seconds,
+                operations_per_second: x.operations_per_second,
+            });
+        }
+
+        let display = file.display;
+        config.format = display
+            .format
+            .map(|x| TableFormat::from_str(&x, true).map_err(|e| anyhow!("Invalid format: {e}")))
+            .transpose()?;
+        config.sections = SectionArgs {
+            include: display
+                .include
+                .iter()
+                .map(|x| x.parse())
+                .collect::<Result<_>>()?,
+            exclude: display
+                .exclude
+                .iter()
+                .map(|x| x.parse())
+                .collect::<Result<_>>()?,
+            percentiles: display
+                .percentiles
+                .iter()
+                .map(|x| parse_percentile(x))
+                .collect::<Result<_>>()?,
+            each_second_window: display.each_second_window,
+            each_second_statistic: display
+                .each_second_statistic
+                .map(|x| {
+                    WindowStatistic::from_str(&x, true)
+                        .map_err(|e| anyhow!("Invalid each_second_statistic: {e}"))
+                })
+                .transpose()?,
+        };
+
+        config.tolerances = file
+            .check
+            .tolerances
+            .map(|x| Tolerances::from_map(&x).context("Invalid tolerance"))
+            .transpose()?;
+        Ok(config)
+    }
+
+    /// Replaces every `@name` in the filter with the filter of the preset of that name.
+    /// Presets may reference other presets, which are expanded in turn.
+    pub(crate) fn expand_presets(&self, query: &str) -> Result<String> {
+        self.expand_presets_within(query, &mut vec![])
+    }
+
+    /// `expanding` holds the presets currently being expanded, so that a preset that references itself is rejected instead of expanding forever
+    fn expand_presets_within<'a>(
+        &'a self,
+        query: &str,
+        expanding: &mut Vec<&'a str>,
+    ) -> Result<String> {
+        let preset_reference = Regex::new(r"(^|[\s(!|,])@([\w-]+)").unwrap();
+        let mut error = None;
+        let expanded = preset_reference.replace_all(query, |captures: &Captures| {
+            if error.is_some() {
+                return String::new();
+            }
+            let name = &captures[2];
+            let Some((name, preset)) = self.presets.get_key_value(name) else {
+                error = Some(self.unknown_preset(name));
+                return String::new();
+            };
+            if expanding.contains(&name.as_str()) {
+                error = Some(anyhow!(
+                    "The filter preset @{name} references itself: {} -> @{name}",
+                    expanding
+                        .iter()
+                        .map(|x| format!("@{x}"))
+                        .collect::<Vec<_>>()
+                        .join(" -> ")
+                ));
+                return String::new();
+            }
+            expanding.push(name);
+            let result = self.expand_presets_within(&query_from_name(preset), expanding);
+            expanding.pop();
+            match result {
+                Ok(preset) => format!("{}({preset})", &captures[1]),
+                Err(e) => {
+                    error = Some(e);
+                    String::new()
+                }
+            }
+        });
+        match error {
+            Some(e) => Err(e),
+            None => Ok(expanded.into_owned()),
+        }
+    }
+
+    fn unknown_preset(&self, name: &str) -> anyhow::Error {
+        anyhow!(
+            "Unknown filter preset @{name}, {}",
+            match &self.path {
+                Some(path) if !self.presets.is_empty() => format!(
+                    "the presets defined in {path:?} are: {}",
+                    self.presets.keys().cloned().collect::<Vec<_>>().join(", ")
+                ),
+                Some(path) => format!("no presets are defined in {path:?}"),
+                None => format!(
+                    "presets are defined in {CONFIG_FILE_NAME} but no config file was found"
+                ),
+            }
+        )
+    }
+
+    /// Fills in the run options that were not specified on the command line with the defaults from the config.
+    /// Only options that apply to every bench are filled in, see [`Config::bench_run_args`] for the rest.
+    pub(crate) fn apply_run_defaults(&self, args: &mut RunArgs) {
+        if args.profilers.is_empty() {
+            args.profilers = self.run.profilers.clone().unwrap_or_default();
+        }
+    }
+
+    /// The run options for a specific bench.
+    /// Options specified on the command line take precedence over the last override matching the bench,
+    /// which takes precedence over the defaults of the config.
+    pub(crate) fn bench_run_args(&self, args: &RunArgs, tags: &Tags) -> RunArgs {
+        let mut args = args.clone();
+        for x in self
+            .overrides
+            .iter()
+            .rev()
+            .filter(|x| x.filter.matches(tags))
+        {
+            args.bench_length_seconds = args.bench_length_seconds.or(x.bench_length_seconds);
+            args.warmup_seconds = args.warmup_seconds.or(x.warmup_seconds);
+            args.operations_per_second = args
+                .operations_per_second
+                .or(x.operations_per_second.map(OperationsPerSecond::Rate));
+        }
+        args.bench_length_seconds = args.bench_length_seconds.or(self.run.bench_length_seconds);
+        args.warmup_seconds = args.warmup_seconds.or(self.run.warmup_seconds);
+        args.operations_per_second = args.operations_per_second.or(self
+            .run
+            .operations_per_second
+            .map(OperationsPerSecond::Rate));
+        if args.load_profile.is_some() {
+            // A load profile given on the command line replaces any rate from the config
+            args.operations_per_second = None;
+        }
+        args
+    }
+
+    /// Fills in the display options that were not specified on the command line with the defaults from the config
+    pub(crate) fn apply_display_defaults(
+        &self,
+        display: &mut DisplayArgs,
+        sections: Option<&mut SectionArgs>,
+    ) {
+        display.format = display.format.or(self.format);
+        if let Some(sections) = sections {
+            if sections.include.is_empty() {
+                sections.include = self.sections.include.clone();
+            }
+            if sections.exclude.is_empty() {
+                sections.exclude = self.sections.exclude.clone();
+            }
+            if sections.percentiles.is_empty() {
+                sections.percentiles = self.sections.percentiles.clone();
+            }
+            sections.each_second_window = sections
+                .each_second_window
+                .or(self.sections.each_second_window);
+            sections.each_second_statistic = sections
+                .each_second_statistic
+                .or(self.sections.each_second_statistic);
+        }
+    }
+
+    /// The tolerances from the `[check.tolerances]` table of the config
+    pub(crate) fn tolerances(&self) -> Option<&Tolerances> {
+        self.tolerances.as_ref()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(presets: &[(&str, &str)]) -> Config {
+        Config {
+            presets: presets
+                .iter()
+                .map(|(name, preset)| (name.to_string(), preset.to_string()))
+                .collect(),
+            ..Config::default()
+        }
+    }
+
+    #[test]
+    fn expand_presets() {
+        let config = config(&[
+            ("single", "topology=single"),
+            ("quick", "@single connection_count=10"),
+            ("quicker", "@quick,db=kafka"),
+        ]);
+        assert_eq!(
+            config.expand_presets("@single !@quick").unwrap(),
+            "(topology=single) !((topology=single) connection_count=10)"
+        );
+        assert_eq!(
+            config.expand_presets("@quicker").unwrap(),
+            "(((topology=single) connection_count=10) db=kafka)"
+        );
+        // an @ within a tag value is not a preset
+        assert_eq!(
+            config.expand_presets("user=a@single").unwrap(),
+            "user=a@single"
+        );
+        assert!(config.expand_presets("@unknown").is_err());
+    }
+
+    #[test]
+    fn expand_presets_cycle() {
+        let config = config(&[("a", "@b db=kafka"), ("b", "@a"), ("c", "@c")]);
+        let error = config.expand_presets("@a").unwrap_err().to_string();
+        assert_eq!(
+            error,
+            "The filter preset @a references itself: @a -> @b -> @a"
+        );
+        assert!(config.expand_presets("@c").is_err());
+    }
+
+    #[test]
+    fn nested_presets_are_validated() {
+        let file = |presets: &[(&str, &str)]| ConfigFile {
+            presets: presets
+                .iter()
+                .map(|(name, preset)| (name.to_string(), preset.to_string()))
+                .collect(),
+            ..ConfigFile::default()
+        };
+        assert!(Config::from_file(file(&[("a", "@b"), ("b", "db=kafka")]), PathBuf::new()).is_ok());
+        assert!(Config::from_file(file(&[("a", "@b")]), PathBuf::new()).is_err());
+        assert!(Config::from_file(file(&[("a", "@a")]), PathBuf::new()).is_err());
+    }
+}
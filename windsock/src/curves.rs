@@ -0,0 +1,258 @@
+use crate::{
+    bench::{OPERATIONS_PER_SECOND_TAG, Tags},
+    charts::MIN_SPARKLINE_WIDTH,
+    filter::Filter,
+    report::{Percentile, ReportArchive},
+    statistic::percentile_name,
+    tables::{reports_in_last_run_matching, terminal_width},
+    units::Unit,
+};
+use anyhow::{Context, Result, anyhow};
+use console::{measure_text_width, pad_str, style};
+use std::collections::BTreeMap;
+
+const PLOT_HEIGHT: usize = 15;
+const PLOT_MAX_WIDTH: usize = 72;
+const AXIS_WIDTH: usize = 7;
+
+/// The results of a bench run at one rate of a sweep
+struct Point {
+    rate: u64,
+    /// The achieved operations per second
+    throughput: f64,
+    /// The latency at the selected percentile, in seconds
+    latency: f64,
+}
+
+/// The results of a bench at every rate of a sweep
+#[derive(Default)]
+struct Curve {
+    current: Vec<Point>,
+    baseline: Vec<Point>,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Cell {
+    Empty,
+    Line,
+    Point,
+}
+
+/// Displays a latency versus throughput curve for every bench matching the filter that was run with a sweep of `--operations-per-second`, overlaid with the baseline.
+pub fn curves(ignore_baseline: bool, filter: &str, percentile: Percentile) -> Result<()> {
+    let filter = Filter::from_query(filter)
+        .with_context(|| format!("Failed to parse tag filter from {:?}", filter))?;
+
+    // The runs of a bench at each rate are grouped under the name of the bench without the rate
+    let mut curves: BTreeMap<String, Curve> = BTreeMap::new();
+    for name in reports_in_last_run_matching(&filter)? {
+        if let Some((bench_name, rate)) = split_rate(&name)? {
+            if let Some(point) = point(&ReportArchive::load(&name)?, rate, percentile) {
+                curves.entry(bench_name).or_default().current.push(point);
+            }
+        }
+    }
+    if curves.is_empty() {
+        return Err(anyhow!(
+            "No benches in the last run that match the filter were run with a sweep of rates, e.g. `--operations-per-second 1000,5000,10000`"
+        ));
+    }
+    if !ignore_baseline {
+        for name in ReportArchive::reports_in_baseline() {
+            if let Some((bench_name, rate)) = split_rate(&name)? {
+                if let Some(curve) = curves.get_mut(&bench_name) {
+                    if let Some(point) = ReportArchive::load_baseline(&name)?
+                        .and_then(|archive| point(&archive, rate, percentile))
+                    {
+                        curve.baseline.push(point);
+                    }
+                }
+            }
+        }
+    }
+
+    let terminal_width = terminal_width();
+    let percentile_name = percentile_name(percentile);
+    for (name, mut curve) in curves {
+        curve.current.sort_by_key(|x| x.rate);
+        curve.baseline.sort_by_key(|x| x.rate);
+
+        println!("{}", style(&name).bold());
+        render_table(&curve, &percentile_name);
+        println!();
+        render_plot(&curve, &percentile_name, terminal_width);
+        println!();
+    }
+    Ok(())
+}
+
+/// Splits the name of a bench run as part of a sweep into the name of the bench and the rate it was run at
+fn split_rate(name: &str) -> Result<Option<(String, u64)>> {
+    let mut tags = Tags::from_name(name)?;
+    Ok(tags
+        .0
+        .remove(OPERATIONS_PER_SECOND_TAG)
+        .and_then(|rate| rate.parse().ok())
+        .map(|rate| (tags.get_name(), rate)))
+}
+
+/// The throughput and latency of operations, or of produced messages for pubsub benches
+fn point(archive: &ReportArchive, rate: u64, percentile: Percentile) -> Option<Point> {
+    if let Some(report) = &archive.operations_report {
+        Some(Point {
+            rate,
+            throughput: report.total_operations_per_second as f64,
+            latency: report.time_percentiles[percentile as usize].as_secs_f64(),
+        })
+    } else {
+        archive.pubsub_report.as_ref().map(|report| Point {
+            rate,
+            throughput: report.produce_per_second as f64,
+            latency: report.produce_time_percentiles[percentile as usize].as_secs_f64(),
+        })
+    }
+}
+
+fn render_table(curve: &Curve, percentile_name: &str) {
+    let mut header = vec![
+        "rate".to_owned(),
+        "achieved".to_owned(),
+        percentile_name.to_owned(),
+    ];
+    if !curve.baseline.is_empty() {
+        header.push("baseline achieved".to_owned());
+        header.push(format!("baseline {percentile_name}"));
+    }
+
+    let mut rows = vec![header];
+    let mut rates: Vec<u64> = curve
+        .current
+        .iter()
+        .chain(&curve.baseline)
+        .map(|x| x.rate)
+        .collect();
+    rates.sort();
+    rates.dedup();
+    for rate in rates {
+        let mut row = vec![Unit::Count.format(rate as f64)];
+        let mut push_point = |points: &[Point]| match points.iter().find(|x| x.rate == rate) {
+            Some(point) => {
+                row.push(Unit::Count.format(point.throughput));
+                row.push(Unit::Seconds.format(point.latency));
+            }
+            None => {
+                row.push("-".to_owned());
+                row.push("-".to_owned());
+            }
+        };
+        push_point(&curve.current);
+        if !curve.baseline.is_empty() {
+            push_point(&curve.baseline);
+        }
+        rows.push(row);
+    }
+
+    let widths: Vec<usize> = (0..rows[0].len())
+        .map(|i| {
+            rows.iter()
+                .map(|row| measure_text_width(&row[i]))
+                .max()
+                .unwrap()
+        })
+        .collect();
+    for (i, row) in rows.iter().enumerate() {
+        let line = row
+            .iter()
+            .zip(&widths)
+            .map(|(value, width)| pad_str(value, *width, console::Alignment::Right, None))
+            .collect::<Vec<_>>()
+            .join("  ");
+        if i == 0 {
+            println!("  {}", style(line).bold());
+        } else {
+            println!("  {line}");
+        }
+    }
+}
+
+/// Plots the latency against the achieved throughput at each rate, with the points of each curve joined by lines
+fn render_plot(curve: &Curve, percentile_name: &str, terminal_width: Option<usize>) {
+    let width = terminal_width
+        .map(|x| x.saturating_sub(AXIS_WIDTH + 4))
+        .unwrap_or(usize::MAX)
+        .clamp(MIN_SPARKLINE_WIDTH, PLOT_MAX_WIDTH);
+    let all = curve.current.iter().chain(&curve.baseline);
+    let max_throughput = all.clone().map(|x| x.throughput).fold(0.0, f64::max);
+    let max_latency = all.map(|x| x.latency).fold(0.0, f64::max);
+    let position = |point: &Point| -> (usize, usize) {
+        let scale = |value: f64, max: f64, size: usize| {
+            if max > 0.0 {
+                (value / max * (size - 1) as f64).round() as usize
+            } else {
+                0
+            }
+        };
+        (
+            scale(point.throughput, max_throughput, width),
+            scale(point.latency, max_latency, PLOT_HEIGHT),
+        )
+    };
+    let plot = |points: &[Point]| -> Vec<Vec<Cell>> {
+        let mut grid = vec![vec![Cell::Empty; width]; PLOT_HEIGHT];
+        for pair in points.windows(2) {
+            let (x0, y0) = position(&pair[0]);
+            let (x1, y1) = position(&pair[1]);
+            let steps = x0.abs_diff(x1).max(y0.abs_diff(y1)).max(1);
+            for step in 0..=steps {
+                let t = step as f64 / steps as f64;
+                let x = (x0 as f64 + (x1 as f64 - x0 as f64) * t).round() as usize;
+                let y = (y0 as f64 + (y1 as f64 - y0 as f64) * t).round() as usize;
+                grid[y][x] = Cell::Line;
+            }
+        }
+        for point in points {
+            let (x, y) = position(point);
+            grid[y][x] = Cell::Point;
+        }
+        grid
+    };
+    let current = plot(&curve.current);
+    let baseline = plot(&curve.baseline);
+
+    println!("  {} vs achieved throughput", style(percentile_name).bold());
+    for row in (0..PLOT_HEIGHT).rev() {
+        let label = if row == PLOT_HEIGHT - 1 {
+            Unit::Seconds.format(max_latency)
+        } else if row == 0 {
+            "0".to_owned()
+        } else {
+            String::new()
+        };
+        let mut line = String::new();
+        for column in 0..width {
+            match (current[row][column], baseline[row][column]) {
+                (Cell::Point, _) => line.push('●'),
+                (Cell::Line, _) => line.push('•'),
+                (Cell::Empty, Cell::Point) => line.push_str(&style('○').dim().to_string()),
+                (Cell::Empty, Cell::Line) => line.push_str(&style('·').dim().to_string()),
+                (Cell::Empty, Cell::Empty) => line.push(' '),
+            }
+        }
+        println!("  {label:>AXIS_WIDTH$} │{line}");
+    }
+    println!("  {:>AXIS_WIDTH$} └{}", "", "─".repeat(width));
+
+    let max_label = format!("{} ops/s", Unit::Count.format(max_throughput));
+    println!(
+        "  {:>AXIS_WIDTH$}  0{}",
+        "",
+        pad_str(&max_label, width - 1, console::Alignment::Right, None)
+    );
+    if !curve.baseline.is_empty() {
+        println!(
+            "  {:>AXIS_WIDTH$}  ● current  {}",
+            "",
+            style("○ baseline").dim()
+        );
+    }
+}
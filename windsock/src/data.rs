@@ -1,4 +1,7 @@
-use std::path::PathBuf;
+use anyhow::{Context, Result, anyhow};
+use fs4::fs_std::FileExt;
+use std::fs::{File, OpenOptions};
+use std::path::{Path, PathBuf};
 use std::sync::OnceLock;
 
 static INITIALIZED_PATH: OnceLock<()> = OnceLock::new();
@@ -30,3 +33,93 @@ fn windsock_path_inner() -> PathBuf {
 pub fn cloud_resources_path() -> PathBuf {
     windsock_path().join("cloud_resources")
 }
+
+/// Held by a windsock process while it is modifying `windsock_data`.
+/// Prevents multiple windsock processes from modifying `windsock_data` at the same time.
+/// The lock is released when dropped.
+pub struct WriteLock {
+    _file: File,
+}
+
+impl WriteLock {
+    /// Acquire the lock, returning an error if another windsock process holds it.
+    pub fn acquire() -> Result<WriteLock> {
+        let (path, file) = Self::open()?;
+        if file
+            .try_lock_exclusive()
+            .with_context(|| format!("Failed to lock {path:?}"))?
+        {
+            remove_stale_tmp_paths();
+            Ok(WriteLock { _file: file })
+        } else {
+            Err(anyhow!(
+                "Another windsock process is currently modifying {:?}.\nWait for it to finish before running this command.",
+                windsock_path()
+            ))
+        }
+    }
+
+    /// Acquire the lock, waiting for any other windsock process holding the lock to release it.
+    pub fn acquire_blocking() -> Result<WriteLock> {
+        let (path, file) = Self::open()?;
+        file.lock_exclusive()
+            .with_context(|| format!("Failed to lock {path:?}"))?;
+        remove_stale_tmp_paths();
+        Ok(WriteLock { _file: file })
+    }
+
+    fn open() -> Result<(PathBuf, File)> {
+        let path = windsock_path().join("lock");
+        let file = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(&path)
+            .with_context(|| format!("Failed to open {path:?}"))?;
+        Ok((path, file))
+    }
+}
+
+/// Writes the file by first writing to a temporary file and then renaming it to `path`.
+/// This ensures that other processes never observe a partially written file.
+pub fn write_atomic(path: &Path, contents: &[u8]) -> std::io::Result<()> {
+    let tmp_path = tmp_path(path, "tmp");
+    std::fs::write(&tmp_path, contents)?;
+    std::fs::rename(&tmp_path, path).inspect_err(|_| {
+        std::fs::remove_file(&tmp_path).ok();
+    })
+}
+
+/// Returns a path in the same directory as `path` that is unique to this process and `purpose`.
+/// The file name contains a `~` which is never produced by `Tags::get_file_name`, so it will never be mistaken for an archive.
+pub fn tmp_path(path: &Path, purpose: &str) -> PathBuf {
+    let mut file_name = path.file_name().unwrap().to_owned();
+    file_name.push(format!("~{purpose}{}", std::process::id()));
+    path.with_file_name(file_name)
+}
+
+/// Removes the temporary files and directories created by [`tmp_path`] that were left behind by a windsock process that crashed.
+/// Only call while holding the [`WriteLock`], as otherwise they may belong to a process that is still running.
+fn remove_stale_tmp_paths() {
+    let root = windsock_path();
+    for dir in [
+        root.join("last_run"),
+        root.join("baseline"),
+        root.join("webpage"),
+        root,
+    ] {
+        let Ok(entries) = std::fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            if entry.file_name().to_string_lossy().contains('~') {
+                let path = entry.path();
+                if path.is_dir() {
+                    std::fs::remove_dir_all(&path).ok();
+                } else {
+                    std::fs::remove_file(&path).ok();
+                }
+            }
+        }
+    }
+}
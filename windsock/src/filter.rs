@@ -1,50 +1,739 @@
-use crate::bench::Tags;
+use crate::bench::{Tags, split_unescaped, unescape};
 use anyhow::{Result, anyhow};
+use regex::Regex;
+use std::ops::Range;
 
-struct FilterTag {
-    key: String,
-    values: Vec<String>,
+/// A parsed tag filter, see the `Tag Filters` section of the CLI help for the grammar.
+pub(crate) struct Filter {
+    expr: Expr,
 }
 
-pub(crate) struct Filter {
-    filter: Vec<FilterTag>,
+enum Expr {
+    And(Vec<Expr>),
+    Or(Vec<Expr>),
+    Not(Box<Expr>),
+    /// `key`, matches benches that have the tag key
+    Exists {
+        key: String,
+    },
+    /// `key=a|b`, matches benches whose value for the tag key matches any of the patterns
+    Equals {
+        key: String,
+        values: Vec<Pattern>,
+    },
+    /// `key>=10`, matches benches whose value for the tag key is a number satisfying the comparison
+    Compare {
+        key: String,
+        operator: Operator,
+        value: f64,
+    },
+}
+
+enum Pattern {
+    Exact(String),
+    /// The tokens of the glob along with its original text
+    Glob(Vec<GlobToken>, String),
+    Regex(Regex),
+}
+
+enum GlobToken {
+    Char(char),
+    /// `?`
+    AnyChar,
+    /// `*`
+    AnyChars,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Operator {
+    Equals,
+    NotEquals,
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+}
+
+impl Operator {
+    fn text(&self) -> &'static str {
+        match self {
+            Operator::Equals => "=",
+            Operator::NotEquals => "!=",
+            Operator::Less => "<",
+            Operator::LessOrEqual => "<=",
+            Operator::Greater => ">",
+            Operator::GreaterOrEqual => ">=",
+        }
+    }
+}
+
+enum Token<'a> {
+    LeftParen,
+    RightParen,
+    Not,
+    Or,
+    Term(&'a str),
 }
 
 impl Filter {
     pub(crate) fn from_query(query: &str) -> Result<Filter> {
-        let mut filter = vec![];
-        for pair in query.split_whitespace() {
-            let mut iter = pair.split('=');
-            let key = iter.next().unwrap().to_owned();
-            let values = match iter.next() {
-                Some(rhs) => rhs.split('|').map(|x| x.to_owned()).collect(),
-                None => {
-                    return Err(anyhow!(
-                        "Expected exactly one '=' but found no '=' in tag {pair:?}"
+        let tokens = tokenize(query)?;
+        let mut parser = Parser {
+            query,
+            tokens,
+            position: 0,
+        };
+        let expr = parser.parse_or()?;
+        if let Some((token, span)) = parser.tokens.get(parser.position) {
+            let message = match token {
+                Token::RightParen => "Unmatched ')'",
+                _ => "Unexpected token",
+            };
+            return Err(parse_error(query, span.clone(), message));
+        }
+        Ok(Filter { expr })
+    }
+
+    pub(crate) fn matches(&self, tags: &Tags) -> bool {
+        self.expr.matches(tags)
+    }
+
+    /// Checks the tag keys and values of the filter against the tags of the benches.
+    /// Returns a description of every key or value that no bench has, with suggestions for what might have been meant.
+    pub(crate) fn problems(&self, benches: &[&Tags]) -> Vec<String> {
+        let mut problems = vec![];
+        self.expr.problems(benches, &mut problems);
+        problems.dedup();
+        problems
+    }
+}
+
+impl Expr {
+    fn matches(&self, tags: &Tags) -> bool {
+        match self {
+            Expr::And(exprs) => exprs.iter().all(|x| x.matches(tags)),
+            Expr::Or(exprs) => exprs.iter().any(|x| x.matches(tags)),
+            Expr::Not(expr) => !expr.matches(tags),
+            Expr::Exists { key } => tags.0.contains_key(key),
+            Expr::Equals { key, values } => match tags.0.get(key) {
+                Some(value) => values.iter().any(|x| x.matches(value)),
+                None => false,
+            },
+            Expr::Compare {
+                key,
+                operator,
+                value,
+            } => match tags.0.get(key).and_then(|x| x.parse::<f64>().ok()) {
+                Some(tag_value) => match operator {
+                    Operator::Less => tag_value < *value,
+                    Operator::LessOrEqual => tag_value <= *value,
+                    Operator::Greater => tag_value > *value,
+                    Operator::GreaterOrEqual => tag_value >= *value,
+                    Operator::Equals | Operator::NotEquals => unreachable!(),
+                },
+                None => false,
+            },
+        }
+    }
+}
+
+impl Expr {
+    fn problems(&self, benches: &[&Tags], problems: &mut Vec<String>) {
+        let key = match self {
+            Expr::And(exprs) | Expr::Or(exprs) => {
+                for expr in exprs {
+                    expr.problems(benches, problems);
+                }
+                return;
+            }
+            Expr::Not(expr) => return expr.problems(benches, problems),
+            Expr::Exists { key } | Expr::Equals { key, .. } | Expr::Compare { key, .. } => key,
+        };
+
+        let mut values: Vec<&str> = benches
+            .iter()
+            .filter_map(|x| x.0.get(key).map(|x| x.as_str()))
+            .collect();
+        values.sort();
+        values.dedup();
+        if values.is_empty() {
+            let mut keys: Vec<&str> = benches
+                .iter()
+                .flat_map(|x| x.0.keys().map(|x| x.as_str()))
+                .collect();
+            keys.sort();
+            keys.dedup();
+            problems.push(format!(
+                "No bench has the tag key {key:?}{}\n    Valid tag keys are: {}",
+                did_you_mean(key, &keys),
+                keys.join(", ")
+            ));
+            return;
+        }
+
+        match self {
+            Expr::Equals {
+                values: patterns, ..
+            } => {
+                for pattern in patterns {
+                    if values.iter().any(|x| pattern.matches(x)) {
+                        continue;
+                    }
+                    let problem = match pattern {
+                        Pattern::Exact(exact) => format!(
+                            "No bench has the value {exact:?} for the tag key {key:?}{}",
+                            did_you_mean(exact, &values)
+                        ),
+                        Pattern::Glob(..) | Pattern::Regex(_) => format!(
+                            "The pattern {:?} matches no values of the tag key {key:?}",
+                            pattern.text()
+                        ),
+                    };
+                    problems.push(format!(
+                        "{problem}\n    Valid values for {key:?} are: {}",
+                        values.join(", ")
                     ));
                 }
-            };
-            if iter.next().is_some() {
-                return Err(anyhow!(
-                    "Expected exactly one '=' but found multiple '=' in tag {pair:?}"
+            }
+            Expr::Compare { operator, .. } if !values.iter().any(|x| x.parse::<f64>().is_ok()) => {
+                problems.push(format!(
+                    "The tag key {key:?} has no numeric values to compare with '{}'\n    Valid values for {key:?} are: {}",
+                    operator.text(),
+                    values.join(", ")
                 ));
             }
-            filter.push(FilterTag { key, values })
+            _ => {}
         }
-        Ok(Filter { filter })
     }
+}
 
-    pub(crate) fn matches(&self, tags: &Tags) -> bool {
-        for FilterTag { key, values } in &self.filter {
-            match tags.0.get(key) {
-                Some(check_value) => {
-                    if !values.contains(check_value) {
-                        return false;
+/// Suggests the candidates most similar to the text, if any are similar enough to be a likely typo
+fn did_you_mean(text: &str, candidates: &[&str]) -> String {
+    let max_distance = (text.chars().count() / 3).max(1);
+    let distances: Vec<(usize, &str)> = candidates
+        .iter()
+        .map(|x| (levenshtein(&text.to_lowercase(), &x.to_lowercase()), *x))
+        .filter(|(distance, _)| *distance <= max_distance)
+        .collect();
+    match distances.iter().map(|(distance, _)| *distance).min() {
+        Some(min) => {
+            let suggestions: Vec<String> = distances
+                .iter()
+                .filter(|(distance, _)| *distance == min)
+                .map(|(_, x)| format!("{x:?}"))
+                .collect();
+            format!(", did you mean {}?", suggestions.join(" or "))
+        }
+        None => String::new(),
+    }
+}
+
+/// The number of single character insertions, deletions or substitutions needed to turn `a` into `b`
+fn levenshtein(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, a) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, b) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a != *b);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+impl Pattern {
+    fn parse(text: &str) -> Result<Pattern, String> {
+        if text.starts_with('/') && regex_end(text, 0) == Some(text.len()) {
+            let regex = &text[1..text.len() - 1];
+            return Regex::new(&regex.replace("\\/", "/"))
+                .map(Pattern::Regex)
+                .map_err(|e| format!("Invalid regex: {e}"));
+        }
+
+        let exact = unescape(text).map_err(|e| e.to_string())?;
+        let mut tokens = vec![];
+        let mut chars = text.chars();
+        while let Some(c) = chars.next() {
+            tokens.push(match c {
+                // unescape has already rejected a trailing `\`
+                '\\' => GlobToken::Char(chars.next().unwrap()),
+                '*' => GlobToken::AnyChars,
+                '?' => GlobToken::AnyChar,
+                c => GlobToken::Char(c),
+            });
+        }
+        if tokens.iter().all(|x| matches!(x, GlobToken::Char(_))) {
+            Ok(Pattern::Exact(exact))
+        } else {
+            Ok(Pattern::Glob(tokens, text.to_owned()))
+        }
+    }
+
+    /// The pattern as it was written in the filter
+    fn text(&self) -> String {
+        match self {
+            Pattern::Exact(exact) => exact.clone(),
+            Pattern::Glob(_, text) => text.clone(),
+            Pattern::Regex(regex) => format!("/{}/", regex.as_str()),
+        }
+    }
+
+    fn matches(&self, value: &str) -> bool {
+        match self {
+            Pattern::Exact(exact) => exact == value,
+            Pattern::Glob(tokens, _) => glob_matches(tokens, &value.chars().collect::<Vec<_>>()),
+            Pattern::Regex(regex) => regex.is_match(value),
+        }
+    }
+}
+
+fn glob_matches(tokens: &[GlobToken], value: &[char]) -> bool {
+    match tokens.split_first() {
+        None => value.is_empty(),
+        Some((GlobToken::AnyChars, rest)) => {
+            (0..=value.len()).any(|skip| glob_matches(rest, &value[skip..]))
+        }
+        Some((GlobToken::AnyChar, rest)) => !value.is_empty() && glob_matches(rest, &value[1..]),
+        Some((GlobToken::Char(c), rest)) => {
+            value.first() == Some(c) && glob_matches(rest, &value[1..])
+        }
+    }
+}
+
+/// Splits the query into tokens along with their byte range in the query.
+///
+/// Parentheses, `!` and `|` are only tokens at the start of a term, so names containing them can still be used as filters.
+/// Within a term every character is taken literally until unescaped whitespace or, inside a group, an unescaped `)`.
+/// Regex values are delimited by `/` and may contain whitespace and parentheses, see [`term_end`].
+fn tokenize(query: &str) -> Result<Vec<(Token<'_>, Range<usize>)>> {
+    let mut tokens = vec![];
+    let mut depth: usize = 0;
+    let mut chars = query.char_indices().peekable();
+    while let Some((start, c)) = chars.next() {
+        match c {
+            c if c.is_whitespace() => {}
+            '(' => {
+                depth += 1;
+                tokens.push((Token::LeftParen, start..start + 1));
+            }
+            ')' => {
+                depth = depth.saturating_sub(1);
+                tokens.push((Token::RightParen, start..start + 1));
+            }
+            '!' => tokens.push((Token::Not, start..start + 1)),
+            '|' => tokens.push((Token::Or, start..start + 1)),
+            _ => {
+                let end = term_end(query, start, depth);
+                while chars.next_if(|(i, _)| *i < end).is_some() {}
+                tokens.push((Token::Term(&query[start..end]), start..end));
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+/// Returns the end of the term beginning at `start`.
+/// A `/` at the start of a value begins a regex which may contain whitespace and parentheses, see [`regex_end`].
+/// If the regex is not closed the `/` is taken literally instead,
+/// so that values such as `mount=/data path=/tmp` can be used without escaping.
+fn term_end(query: &str, start: usize, depth: usize) -> usize {
+    let mut end = start;
+    let mut previous = None;
+    let mut escaped = false;
+    while let Some(c) = query[end..].chars().next() {
+        if escaped {
+            escaped = false;
+        } else if c == '\\' {
+            escaped = true;
+        } else if c == '/' && matches!(previous, Some('=' | '|')) {
+            if let Some(regex_end) = regex_end(query, end) {
+                end = regex_end;
+                previous = Some('/');
+                continue;
+            }
+        } else if c.is_whitespace() || (c == ')' && depth > 0) {
+            break;
+        }
+        previous = Some(c);
+        end += c.len_utf8();
+    }
+    end
+}
+
+/// If the `/` at `slash` begins a regex, returns the index just after the `/` that closes it.
+/// A regex is closed by the next unescaped `/`, which must be followed by whitespace, `|`, `)` or the end of `text`.
+fn regex_end(text: &str, slash: usize) -> Option<usize> {
+    let mut escaped = false;
+    for (i, c) in text[slash + 1..].char_indices() {
+        if escaped {
+            escaped = false;
+        } else if c == '\\' {
+            escaped = true;
+        } else if c == '/' {
+            let end = slash + 1 + i + 1;
+            return text[end..]
+                .chars()
+                .next()
+                .is_none_or(|next| next.is_whitespace() || matches!(next, '|' | ')'))
+                .then_some(end);
+        }
+    }
+    None
+}
+
+struct Parser<'a> {
+    query: &'a str,
+    tokens: Vec<(Token<'a>, Range<usize>)>,
+    position: usize,
+}
+
+impl Parser<'_> {
+    /// `and ('|' and)*`
+    fn parse_or(&mut self) -> Result<Expr> {
+        let mut alternatives = vec![self.parse_and()?];
+        while let Some((Token::Or, span)) = self.tokens.get(self.position) {
+            let span = span.clone();
+            self.position += 1;
+            let alternative = self.parse_and()?;
+            if matches!(&alternative, Expr::And(x) if x.is_empty()) {
+                return Err(parse_error(
+                    self.query,
+                    span,
+                    "Expected a tag filter after '|'",
+                ));
+            }
+            alternatives.push(alternative);
+        }
+        if alternatives.len() == 1 {
+            Ok(alternatives.pop().unwrap())
+        } else {
+            Ok(Expr::Or(alternatives))
+        }
+    }
+
+    /// `unary*`, an empty sequence matches every bench
+    fn parse_and(&mut self) -> Result<Expr> {
+        let mut exprs = vec![];
+        while let Some((token, _)) = self.tokens.get(self.position) {
+            if matches!(token, Token::Or | Token::RightParen) {
+                break;
+            }
+            exprs.push(self.parse_unary()?);
+        }
+        if exprs.len() == 1 {
+            Ok(exprs.pop().unwrap())
+        } else {
+            Ok(Expr::And(exprs))
+        }
+    }
+
+    /// `'!' unary | '(' or ')' | term`
+    fn parse_unary(&mut self) -> Result<Expr> {
+        let (token, span) = &self.tokens[self.position];
+        let span = span.clone();
+        self.position += 1;
+        match token {
+            Token::Not => match self.tokens.get(self.position) {
+                Some((Token::Not | Token::LeftParen | Token::Term(_), _)) => {
+                    Ok(Expr::Not(Box::new(self.parse_unary()?)))
+                }
+                _ => Err(parse_error(
+                    self.query,
+                    span,
+                    "Expected a tag filter after '!'",
+                )),
+            },
+            Token::LeftParen => {
+                let expr = self.parse_or()?;
+                if matches!(&expr, Expr::And(x) if x.is_empty()) {
+                    return Err(parse_error(self.query, span, "Empty parentheses"));
+                }
+                match self.tokens.get(self.position) {
+                    Some((Token::RightParen, _)) => {
+                        self.position += 1;
+                        Ok(expr)
                     }
+                    _ => Err(parse_error(self.query, span, "Unclosed '('")),
+                }
+            }
+            Token::Term(term) => parse_term(self.query, term, span),
+            Token::Or | Token::RightParen => unreachable!(),
+        }
+    }
+}
+
+fn parse_term(query: &str, term: &str, span: Range<usize>) -> Result<Expr> {
+    let Some((index, operator)) = find_operator(term) else {
+        return Ok(Expr::Exists {
+            key: unescape(term).map_err(|e| parse_error(query, span, &e.to_string()))?,
+        });
+    };
+    let key = unescape(&term[..index])
+        .map_err(|e| parse_error(query, span.start..span.start + index, &e.to_string()))?;
+    let value_start = index + operator.text().len();
+    let value = &term[value_start..];
+    let value_span = span.start + value_start..span.end;
+    if key.is_empty() {
+        return Err(parse_error(
+            query,
+            span.start..span.start + value_start,
+            "Expected a tag key before the operator",
+        ));
+    }
+    if value.is_empty() {
+        return Err(parse_error(
+            query,
+            span.start + index..span.start + value_start,
+            &format!("Expected a value after '{}'", operator.text()),
+        ));
+    }
+
+    match operator {
+        Operator::Equals | Operator::NotEquals => {
+            let mut values = vec![];
+            let mut offset = value_start;
+            for text in split_values(value) {
+                let text_span = span.start + offset..span.start + offset + text.len();
+                offset += text.len() + 1;
+                if !text.starts_with('/') && split_unescaped(text, '=').nth(1).is_some() {
+                    return Err(parse_error(
+                        query,
+                        text_span,
+                        "Unexpected '=' in value, a '=' that is part of a tag value must be escaped as '\\='",
+                    ));
                 }
-                None => return false,
+                values.push(
+                    Pattern::parse(text)
+                        .map_err(|message| parse_error(query, text_span, &message))?,
+                );
             }
+            let expr = Expr::Equals { key, values };
+            if operator == Operator::NotEquals {
+                Ok(Expr::Not(Box::new(expr)))
+            } else {
+                Ok(expr)
+            }
+        }
+        operator => match unescape(value)
+            .map_err(|e| parse_error(query, value_span.clone(), &e.to_string()))?
+            .parse::<f64>()
+        {
+            Ok(value) => Ok(Expr::Compare {
+                key,
+                operator,
+                value,
+            }),
+            Err(_) => Err(parse_error(
+                query,
+                value_span,
+                &format!("Expected a number after '{}'", operator.text()),
+            )),
+        },
+    }
+}
+
+/// Finds the first unescaped operator in the term, returning its byte index
+fn find_operator(term: &str) -> Option<(usize, Operator)> {
+    let bytes = term.as_bytes();
+    let mut escaped = false;
+    for (i, byte) in bytes.iter().enumerate() {
+        if escaped {
+            escaped = false;
+            continue;
+        }
+        let next_is_equals = bytes.get(i + 1) == Some(&b'=');
+        let operator = match byte {
+            b'\\' => {
+                escaped = true;
+                continue;
+            }
+            b'=' => Operator::Equals,
+            b'!' if next_is_equals => Operator::NotEquals,
+            b'<' if next_is_equals => Operator::LessOrEqual,
+            b'<' => Operator::Less,
+            b'>' if next_is_equals => Operator::GreaterOrEqual,
+            b'>' => Operator::Greater,
+            _ => continue,
+        };
+        return Some((i, operator));
+    }
+    None
+}
+
+/// Splits the values on unescaped `|` that are not within a regex
+fn split_values(values: &str) -> Vec<&str> {
+    let mut result = vec![];
+    let mut start = 0;
+    let mut escaped = false;
+    let mut chars = values.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        if escaped {
+            escaped = false;
+        } else if c == '\\' {
+            escaped = true;
+        } else if c == '/' && i == start {
+            if let Some(end) = regex_end(values, i) {
+                while chars.next_if(|(i, _)| *i < end).is_some() {}
+            }
+        } else if c == '|' {
+            result.push(&values[start..i]);
+            start = i + 1;
+        }
+    }
+    result.push(&values[start..]);
+    result
+}
+
+/// Creates an error pointing at the offending part of the query with carets
+fn parse_error(query: &str, span: Range<usize>, message: &str) -> anyhow::Error {
+    let indent = query[..span.start].chars().count();
+    let length = query[span].chars().count().max(1);
+    anyhow!(
+        "{message}\n    {query}\n    {}{}",
+        " ".repeat(indent),
+        "^".repeat(length)
+    )
+}
+
+/// Converts a bench name into a filter that matches that bench by swapping the unescaped commas for spaces.
+pub(crate) fn query_from_name(name: &str) -> String {
+    split_unescaped(name, ',').collect::<Vec<_>>().join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tags(pairs: &[(&str, &str)]) -> Tags {
+        Tags(
+            pairs
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect(),
+        )
+    }
+
+    fn matches(query: &str, tags: &Tags) -> bool {
+        Filter::from_query(query).unwrap().matches(tags)
+    }
+
+    #[test]
+    fn names_are_filters() {
+        for pairs in [
+            &[("db", "kafka"), ("topology", "single")][..],
+            &[("mount", "/data")],
+            &[("path", "/tmp/")],
+            &[("path", "a*b?c")],
+            &[("a,b", "c=d|e\\f"), ("x", "(y)")],
+            &[("mount", "/data"), ("regexish", "/a/b/")],
+            &[
+                ("a!", "b"),
+                ("c<", "d"),
+                ("e>", "=f"),
+                ("(g", "h)"),
+                ("!i", "j"),
+            ],
+            &[(")k", "l"), ("m!=n", "o"), ("p<=q", ">r")],
+        ] {
+            let bench = tags(pairs);
+            let name = bench.get_name();
+            assert_eq!(Tags::from_name(&name).unwrap().0, bench.0);
+            assert!(
+                matches(&query_from_name(&name), &bench),
+                "{name} did not match"
+            );
+        }
+
+        // the name of a bench whose value merely looks like a pattern does not match other benches
+        let name = tags(&[("path", "/tmp/")]).get_name();
+        assert!(!matches(
+            &query_from_name(&name),
+            &tags(&[("path", "/var/tmp/x")])
+        ));
+        let name = tags(&[("path", "a*")]).get_name();
+        assert!(!matches(&query_from_name(&name), &tags(&[("path", "ab")])));
+        let name = tags(&[("a!", "b")]).get_name();
+        assert_eq!(name, r"a\!=b");
+        assert!(!matches(&query_from_name(&name), &tags(&[("a", "c")])));
+    }
+
+    #[test]
+    fn unclosed_slash_is_literal() {
+        let bench = tags(&[("mount", "/data")]);
+        assert!(matches("mount=/data", &bench));
+        assert!(matches(
+            "mount=/data db",
+            &tags(&[("mount", "/data"), ("db", "x")])
+        ));
+        assert!(!matches("mount=/dat", &bench));
+
+        // a slash in a later term does not close the first
+        let bench = tags(&[("mount", "/data"), ("path", "/tmp")]);
+        assert!(matches("mount=/data path=/tmp", &bench));
+        assert!(matches("(mount=/data) path=/tmp", &bench));
+        assert!(matches("mount=/other/|/data path=/tmp", &bench));
+        assert!(matches("mount=/data|/other/ path=/tmp", &bench));
+        assert!(matches("path=/tmp/x/", &tags(&[("path", "/tmp/x/")])));
+        assert!(!matches("path=/tmp/x/", &tags(&[("path", "tmp/x")])));
+        assert!(!matches(
+            "mount=/data path=/tmp",
+            &tags(&[("mount", "/data path=/tmp")])
+        ));
+    }
+
+    #[test]
+    fn regex() {
+        let bench = tags(&[("topology", "cluster3")]);
+        assert!(matches(r"topology=/^cluster\d$/", &bench));
+        assert!(matches("topology=/cluster 1|cluster3/", &bench));
+        assert!(matches("topology=single|/clus/", &bench));
+        assert!(!matches("topology=/^single/", &bench));
+        assert!(Filter::from_query("topology=/(/").is_err());
+    }
+
+    #[test]
+    fn globs() {
+        let bench = tags(&[("topology", "cluster3")]);
+        assert!(matches("topology=cluster*", &bench));
+        assert!(matches("topology=cluste?3", &bench));
+        assert!(!matches(
+            "topology=cluster?",
+            &tags(&[("topology", "cluster")])
+        ));
+        assert!(!matches(r"topology=cluster\*", &bench));
+        assert!(matches(r"topology=a\*", &tags(&[("topology", "a*")])));
+    }
+
+    #[test]
+    fn operators() {
+        let bench = tags(&[("db", "kafka"), ("connections", "100")]);
+        assert!(matches("db=kafka|redis", &bench));
+        assert!(matches("db!=redis", &bench));
+        assert!(!matches("db!=kafka", &bench));
+        assert!(matches("connections>=100", &bench));
+        assert!(!matches("connections<100", &bench));
+        assert!(matches("connections", &bench));
+        assert!(!matches("!connections", &bench));
+        assert!(matches("(db=redis | connections>50) db", &bench));
+        assert!(matches("", &bench));
+    }
+
+    #[test]
+    fn errors() {
+        for query in [
+            "db=",
+            "=kafka",
+            "(db=kafka",
+            "(db=kafka))",
+            "db=a=b",
+            "connections>=many",
+            r"db=kafka\",
+            r"db=kafka*\",
+            "db=kafka |",
+        ] {
+            assert!(Filter::from_query(query).is_err(), "{query} parsed");
         }
-        true
     }
 }
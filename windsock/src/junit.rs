@@ -0,0 +1,358 @@
+use crate::{
+    check::BenchCheck, data::write_atomic, report::ReportArchive, statistic::Statistic,
+    units::format_seconds,
+};
+use anyhow::{Context, Result};
+use console::style;
+use std::{fmt::Write, path::Path, time::Duration};
+use time::{OffsetDateTime, format_description::well_known::Rfc3339};
+
+/// The statistics included as properties of every testcase, when the bench reports them
+const PROPERTY_STATISTICS: [&str; 11] = [
+    "ops",
+    "total",
+    "errors",
+    "error_rate",
+    "mean",
+    "p50",
+    "p99",
+    "max",
+    "produce",
+    "consume",
+    "backlog",
+];
+
+/// What happened to a bench during a run
+pub(crate) enum Outcome {
+    /// The bench ran to completion, its results are in the last run
+    Completed { time: Duration },
+    /// The bench could not be run
+    Failed {
+        time: Duration,
+        /// The first line of the root cause of the failure
+        reason: String,
+        /// The full error, including the output of the bench
+        error: String,
+    },
+    /// The bench took longer than `--timeout-seconds` and was abandoned
+    TimedOut { time: Duration, limit: Duration },
+    /// The bench was not run, e.g. because an earlier bench failed
+    Skipped { reason: &'static str },
+}
+
+/// Collects the outcome of each bench in a run so that they can be summarised and written as a JUnit report
+#[derive(Default)]
+pub(crate) struct RunOutcomes(Vec<(String, Outcome)>);
+
+impl RunOutcomes {
+    pub(crate) fn push(&mut self, name: String, outcome: Outcome) {
+        self.0.push((name, outcome));
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Prints a table of every bench in the run, whether it passed, failed or was skipped and why
+    pub(crate) fn print_summary(&self) {
+        if self.0.is_empty() {
+            return;
+        }
+        let name_width = self.0.iter().map(|(name, _)| name.len()).max().unwrap();
+        let (mut passed, mut failed, mut timed_out, mut skipped) = (0, 0, 0, 0);
+        println!();
+        println!("{}", style("Run summary").bold());
+        for (name, outcome) in &self.0 {
+            let (status, time, reason) = match outcome {
+                Outcome::Completed { time } => {
+                    passed += 1;
+                    (style("passed ").green(), Some(*time), "")
+                }
+                Outcome::Failed { time, reason, .. } => {
+                    failed += 1;
+                    (style("failed ").red(), Some(*time), reason.as_str())
+                }
+                Outcome::TimedOut { time, .. } => {
+                    timed_out += 1;
+                    (style("timeout").red(), Some(*time), "")
+                }
+                Outcome::Skipped { reason } => {
+                    skipped += 1;
+                    (style("skipped").yellow(), None, *reason)
+                }
+            };
+            let time = time
+                .map(|x| format_seconds(x.as_secs()))
+                .unwrap_or_default();
+            println!("  {status}  {name:name_width$}  {time:>8}  {reason}");
+        }
+        if timed_out > 0 {
+            println!("{passed} passed, {failed} failed, {timed_out} timed out, {skipped} skipped");
+        } else {
+            println!("{passed} passed, {failed} failed, {skipped} skipped");
+        }
+    }
+
+    /// Writes a JUnit report with a testcase for every bench.
+    /// Benches that failed to run or that encountered errors while running are marked as failures.
+    pub(crate) fn write(&self, path: &Path, suite_name: &str) -> Result<()> {
+        let cases = self
+            .0
+            .iter()
+            .map(|(name, outcome)| match outcome {
+                Outcome::Completed { time } => match ReportArchive::load(name) {
+                    Ok(archive) => TestCase::from_archive(name, *time, &archive),
+                    Err(err) => TestCase {
+                        name: name.clone(),
+                        time: *time,
+                        result: TestResult::Failure {
+                            message: "Bench did not produce any results".to_owned(),
+                            details: format!("{err:?}"),
+                        },
+                        properties: vec![],
+                        output: vec![],
+                    },
+                },
+                Outcome::Failed { time, error, .. } => TestCase {
+                    name: name.clone(),
+                    time: *time,
+                    result: TestResult::Failure {
+                        message: "Bench failed to run".to_owned(),
+                        details: error.clone(),
+                    },
+                    properties: vec![],
+                    output: vec![],
+                },
+                Outcome::TimedOut { time, limit } => TestCase {
+                    name: name.clone(),
+                    time: *time,
+                    result: TestResult::Error {
+                        message: format!(
+                            "Bench timed out after {}",
+                            format_seconds(limit.as_secs())
+                        ),
+                        kind: "timeout",
+                    },
+                    properties: vec![],
+                    output: vec![],
+                },
+                Outcome::Skipped { reason } => TestCase {
+                    name: name.clone(),
+                    time: Duration::ZERO,
+                    result: TestResult::Skipped {
+                        message: reason.to_string(),
+                    },
+                    properties: vec![],
+                    output: vec![],
+                },
+            })
+            .collect();
+        write_report(path, suite_name, cases)
+    }
+}
+
+/// Writes a JUnit report with a testcase for every bench checked.
+/// Benches that violated their tolerances are marked as failures.
+pub(crate) fn write_check_report(path: &Path, checks: &[BenchCheck]) -> Result<()> {
+    let cases = checks
+        .iter()
+        .map(|check| {
+            let mut case = TestCase::from_archive(&check.name, Duration::ZERO, &check.archive);
+            // Errors during the bench are handled by the tolerances, so only the violations determine the result
+            case.result = if check.violations.is_empty() {
+                TestResult::Success
+            } else {
+                TestResult::Failure {
+                    message: format!("{} tolerances violated", check.violations.len()),
+                    details: check.violations.join("\n"),
+                }
+            };
+            if !check.has_baseline {
+                case.output
+                    .push("No baseline, only absolute tolerances were checked".to_owned());
+            }
+            case.output.extend(check.unchecked.iter().cloned());
+            case
+        })
+        .collect();
+    write_report(path, "check", cases)
+}
+
+struct TestCase {
+    name: String,
+    time: Duration,
+    result: TestResult,
+    properties: Vec<(String, String)>,
+    output: Vec<String>,
+}
+
+enum TestResult {
+    Success,
+    Failure {
+        message: String,
+        details: String,
+    },
+    /// The bench could not complete for a reason other than its own failure, e.g. a timeout
+    Error {
+        message: String,
+        kind: &'static str,
+    },
+    Skipped {
+        message: String,
+    },
+}
+
+impl TestCase {
+    fn from_archive(name: &str, time: Duration, archive: &ReportArchive) -> Self {
+        let mut properties = vec![];
+        for statistic in PROPERTY_STATISTICS {
+            let statistic: Statistic = statistic.parse().unwrap();
+            if let Some(measured) = statistic.measure(archive) {
+                properties.push((statistic.to_string(), measured.display));
+            }
+        }
+        for metric in &archive.metrics {
+            let statistic = Statistic::Metric(metric.name().to_owned());
+            if let Some(measured) = statistic.measure(archive) {
+                properties.push((statistic.to_string(), measured.display));
+            }
+        }
+
+        let result = if !archive.running_in_release {
+            TestResult::Failure {
+                message: "Bench results invalid! Bench compiled with non-release profile"
+                    .to_owned(),
+                details: String::new(),
+            }
+        } else if !archive.error_messages.is_empty() {
+            TestResult::Failure {
+                message: "Bench encountered errors".to_owned(),
+                details: archive.error_messages.join("\n"),
+            }
+        } else {
+            TestResult::Success
+        };
+
+        TestCase {
+            name: name.to_owned(),
+            time,
+            result,
+            properties,
+            output: archive.info_messages.clone(),
+        }
+    }
+}
+
+fn write_report(path: &Path, suite_name: &str, cases: Vec<TestCase>) -> Result<()> {
+    let failures = cases
+        .iter()
+        .filter(|x| matches!(x.result, TestResult::Failure { .. }))
+        .count();
+    let errors = cases
+        .iter()
+        .filter(|x| matches!(x.result, TestResult::Error { .. }))
+        .count();
+    let skipped = cases
+        .iter()
+        .filter(|x| matches!(x.result, TestResult::Skipped { .. }))
+        .count();
+    let time: f64 = cases.iter().map(|x| x.time.as_secs_f64()).sum();
+    let timestamp = OffsetDateTime::now_utc().format(&Rfc3339).unwrap();
+
+    let mut xml = String::new();
+    writeln!(xml, r#"<?xml version="1.0" encoding="UTF-8"?>"#).unwrap();
+    writeln!(
+        xml,
+        r#"<testsuites name="windsock" tests="{}" failures="{failures}" errors="{errors}" skipped="{skipped}" time="{time:.3}">"#,
+        cases.len()
+    )
+    .unwrap();
+    writeln!(
+        xml,
+        r#"  <testsuite name="{}" tests="{}" failures="{failures}" errors="{errors}" skipped="{skipped}" time="{time:.3}" timestamp="{timestamp}">"#,
+        escape(suite_name),
+        cases.len()
+    )
+    .unwrap();
+    for case in &cases {
+        writeln!(
+            xml,
+            r#"    <testcase name="{}" classname="windsock.{}" time="{:.3}">"#,
+            escape(&case.name),
+            escape(suite_name),
+            case.time.as_secs_f64()
+        )
+        .unwrap();
+        if !case.properties.is_empty() {
+            writeln!(xml, "      <properties>").unwrap();
+            for (name, value) in &case.properties {
+                writeln!(
+                    xml,
+                    r#"        <property name="{}" value="{}"/>"#,
+                    escape(name),
+                    escape(value)
+                )
+                .unwrap();
+            }
+            writeln!(xml, "      </properties>").unwrap();
+        }
+        match &case.result {
+            TestResult::Success => {}
+            TestResult::Failure { message, details } => writeln!(
+                xml,
+                r#"      <failure message="{}">{}</failure>"#,
+                escape(message),
+                escape(details)
+            )
+            .unwrap(),
+            TestResult::Error { message, kind } => writeln!(
+                xml,
+                r#"      <error message="{}" type="{kind}"/>"#,
+                escape(message)
+            )
+            .unwrap(),
+            TestResult::Skipped { message } => {
+                writeln!(xml, r#"      <skipped message="{}"/>"#, escape(message)).unwrap()
+            }
+        }
+        if !case.output.is_empty() {
+            writeln!(
+                xml,
+                "      <system-out>{}</system-out>",
+                escape(&case.output.join("\n"))
+            )
+            .unwrap();
+        }
+        writeln!(xml, "    </testcase>").unwrap();
+    }
+    writeln!(xml, "  </testsuite>").unwrap();
+    writeln!(xml, "</testsuites>").unwrap();
+
+    if let Some(parent) = path.parent().filter(|x| !x.as_os_str().is_empty()) {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create directory {parent:?}"))?;
+    }
+    write_atomic(path, xml.as_bytes())
+        .with_context(|| format!("Failed to write JUnit report to {path:?}"))?;
+    println!("JUnit report written to {}", path.display());
+    Ok(())
+}
+
+/// Escapes text for use in xml attributes and text content.
+/// Characters that are not allowed in xml at all, such as most control characters, are dropped.
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            '\n' | '\t' | '\r' => escaped.push(c),
+            c if c.is_control() => {}
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
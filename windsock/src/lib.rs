@@ -1,27 +1,53 @@
+mod affinity;
 mod bench;
+mod browse;
+mod capacity;
+mod charts;
+mod check;
 mod cli;
 pub mod cloud;
+mod config;
+mod curves;
 mod data;
 mod filter;
+mod junit;
 mod list;
+mod load_profile;
+mod order;
+mod plan;
 mod report;
+mod statistic;
 mod tables;
+mod units;
+mod webpage;
 
+pub use affinity::CpuSet;
 pub use bench::{Bench, BenchParameters, BenchTask, Profiling};
-use data::cloud_resources_path;
+use data::{WriteLock, cloud_resources_path};
+pub use load_profile::LoadProfile;
 pub use report::{
     ExternalReport, LatencyPercentile, Metric, OperationsReport, PubSubReport, Report,
     ReportArchive,
 };
 pub use tables::Goal;
+pub use units::Unit;
 
-use anyhow::{Result, anyhow};
-use bench::BenchState;
+use anyhow::{Context, Result, anyhow};
+use bench::{BenchState, Tags, TimedOut};
+use capacity::DEFAULT_START_RATE;
+use check::Tolerances;
 use clap::{CommandFactory, Parser};
-use cli::{Command, RunArgs, WindsockArgs};
+use cli::{CapacityArgs, Command, OperationsPerSecond, RunArgs, WindsockArgs};
 use cloud::{BenchInfo, Cloud};
-use filter::Filter;
+use config::Config;
+use console::style;
+use filter::{Filter, query_from_name};
+use junit::{Outcome, RunOutcomes};
+use order::{describe_order, order_runs, random_seed};
+use plan::{Plan, PlannedBench};
+use std::collections::HashSet;
 use std::process::exit;
+use std::time::Instant;
 use tokio::runtime::Runtime;
 
 /// Takes control of your application, providing a CLI into your benchmarks.
@@ -29,6 +55,7 @@ pub struct Windsock<ResourcesRequired, Resources> {
     benches: Vec<BenchState<ResourcesRequired, Resources>>,
     cloud: Box<dyn Cloud<CloudResourcesRequired = ResourcesRequired, CloudResources = Resources>>,
     running_in_release: bool,
+    config: Config,
 }
 
 impl<ResourcesRequired: Clone, Resources: Clone> Windsock<ResourcesRequired, Resources> {
@@ -40,6 +67,8 @@ impl<ResourcesRequired: Clone, Resources: Clone> Windsock<ResourcesRequired, Res
     /// * Cloud: The benches will be run in an order optimized according to its required cloud resources.
     ///
     /// `release_profiles` specifies which cargo profiles Windsock will run under, if a different profile is used windsock will refuse to run.
+    ///
+    /// Panics if any bench has invalid tags or if multiple benches share the same name.
     pub fn new(
         benches: Vec<
             Box<dyn Bench<CloudResourcesRequired = ResourcesRequired, CloudResources = Resources>>,
@@ -51,10 +80,25 @@ impl<ResourcesRequired: Clone, Resources: Clone> Windsock<ResourcesRequired, Res
     ) -> Self {
         let running_in_release = release_profiles.contains(&env!("PROFILE"));
 
+        let benches: Vec<_> = benches.into_iter().map(BenchState::new).collect();
+        let mut names = HashSet::new();
+        for bench in &benches {
+            if let Err(err) = bench.tags.validate() {
+                panic!("The bench with tags {:?} is invalid: {err}", bench.tags.0);
+            }
+            let name = bench.tags.get_name();
+            if !names.insert(name.clone()) {
+                panic!(
+                    "Multiple benches have the name {name:?}, every bench must have a unique set of tags"
+                );
+            }
+        }
+
         Windsock {
-            benches: benches.into_iter().map(BenchState::new).collect(),
+            benches,
             cloud,
             running_in_release,
+            config: Config::default(),
         }
     }
 
@@ -74,7 +118,41 @@ impl<ResourcesRequired: Clone, Resources: Clone> Windsock<ResourcesRequired, Res
         let args = WindsockArgs::parse();
 
         let running_in_release = self.running_in_release;
-        if let Some(command) = args.command {
+        if let Some(mut command) = args.command {
+            // internal-run is given its options explicitly by the windsock process that launched it
+            if !matches!(command, Command::InternalRun(_)) {
+                self.config = Config::load()?;
+            }
+            if let Some(filter) = command.tag_filter_mut() {
+                *filter = self.config.expand_presets(filter)?;
+            }
+            match &command {
+                Command::CloudRun(args) | Command::CloudSetupRunCleanup(args) => {
+                    args.validate(true)?;
+                    pin_orchestrator(args)?;
+                }
+                Command::LocalRun(args) => {
+                    args.validate(false)?;
+                    pin_orchestrator(args)?;
+                }
+                _ => {}
+            }
+            let _lock = if command.modifies_windsock_data() {
+                Some(WriteLock::acquire()?)
+            } else {
+                None
+            };
+            if let Some(filter) = command.tag_filter() {
+                // The last run may contain benches that are no longer registered, so only commands that run benches require a match
+                let requires_match = matches!(
+                    command,
+                    Command::CloudSetup { .. }
+                        | Command::CloudRun(_)
+                        | Command::CloudSetupRunCleanup(_)
+                        | Command::LocalRun(_)
+                );
+                self.validate_filter(&filter, requires_match)?;
+            }
             match command {
                 Command::List => list::list(&self.benches),
                 Command::BaselineSet => {
@@ -85,39 +163,166 @@ impl<ResourcesRequired: Clone, Resources: Clone> Windsock<ResourcesRequired, Res
                     ReportArchive::clear_baseline();
                     println!("Baseline cleared");
                 }
-                Command::GenerateWebpage => {
-                    println!("Webpage generation is not implemented yet!")
-                }
+                Command::GenerateWebpage {
+                    ignore_baseline,
+                    compare_tags,
+                    filter,
+                } => webpage::generate_webpage(
+                    ignore_baseline,
+                    &query_from_name(&filter.unwrap_or_default()),
+                    &compare_tags,
+                )?,
                 Command::Results {
                     ignore_baseline,
+                    summary,
+                    sort_by,
+                    descending,
+                    mut display,
+                    mut sections,
+                    filter,
+                } => {
+                    self.config
+                        .apply_display_defaults(&mut display, Some(&mut sections));
+                    let filter = query_from_name(&filter.unwrap_or_default());
+                    if summary {
+                        tables::summary(
+                            ignore_baseline,
+                            &filter,
+                            &sort_by,
+                            descending,
+                            display.table_format(),
+                        )?
+                    } else {
+                        tables::results(
+                            ignore_baseline,
+                            &filter,
+                            display.table_format(),
+                            &sections,
+                        )?
+                    }
+                }
+                Command::CompareByName {
+                    filter,
+                    mut display,
+                    mut sections,
+                } => {
+                    self.config
+                        .apply_display_defaults(&mut display, Some(&mut sections));
+                    tables::compare_by_name(&filter, display.table_format(), &sections)?
+                }
+                Command::CompareByTags {
+                    filter,
+                    mut display,
+                    mut sections,
+                } => {
+                    self.config
+                        .apply_display_defaults(&mut display, Some(&mut sections));
+                    tables::compare_by_tags(&filter, display.table_format(), &sections)?
+                }
+                Command::Pivot {
+                    rows,
+                    columns,
+                    statistic,
+                    ignore_baseline,
+                    mut display,
+                    filter,
+                } => {
+                    self.config.apply_display_defaults(&mut display, None);
+                    tables::pivot(
+                        ignore_baseline,
+                        &query_from_name(&filter.unwrap_or_default()),
+                        &rows,
+                        &columns,
+                        &statistic,
+                        display.table_format(),
+                    )?
+                }
+                Command::Charts {
+                    ignore_baseline,
+                    latency,
                     filter,
-                } => tables::results(
+                } => charts::charts(
                     ignore_baseline,
-                    &filter.unwrap_or_default().replace(',', " "),
+                    &query_from_name(&filter.unwrap_or_default()),
+                    latency,
                 )?,
-                Command::CompareByName { filter } => tables::compare_by_name(&filter)?,
-                Command::CompareByTags { filter } => tables::compare_by_tags(&filter)?,
+                Command::Curves {
+                    ignore_baseline,
+                    percentile,
+                    filter,
+                } => curves::curves(
+                    ignore_baseline,
+                    &query_from_name(&filter.unwrap_or_default()),
+                    percentile,
+                )?,
+                Command::Browse { filter } => browse::browse(&filter.unwrap_or_default())?,
+                Command::Check {
+                    tolerances,
+                    junit,
+                    filter,
+                } => {
+                    let loaded;
+                    let tolerances = match tolerances {
+                        Some(path) => {
+                            loaded = Tolerances::load(&path)?;
+                            &loaded
+                        }
+                        None => self.config.tolerances().ok_or_else(|| {
+                            anyhow!(
+                                "No tolerances specified, use --tolerances or add a [check.tolerances] table to windsock.toml"
+                            )
+                        })?,
+                    };
+                    check::check(
+                        &query_from_name(&filter.unwrap_or_default()),
+                        tolerances,
+                        junit.as_deref(),
+                    )?
+                }
                 Command::CloudSetup { filter } => {
                     create_runtime(None).block_on(self.cloud_setup(filter))?
                 }
-                Command::CloudRun(args) => {
-                    create_runtime(None).block_on(self.cloud_run(args, running_in_release))?;
+                Command::CloudRun(mut args) => {
+                    self.config.apply_run_defaults(&mut args);
+                    if args.dry_run {
+                        self.dry_run(
+                            &args,
+                            Some("Cloud resources would be loaded from the file stored by `cloud-setup`."),
+                        )?
+                    } else {
+                        create_runtime(None).block_on(self.cloud_run(args, running_in_release))?;
+                    }
                 }
                 Command::CloudCleanup => {
                     create_runtime(None).block_on(self.cloud_cleanup());
                 }
-                Command::CloudSetupRunCleanup(args) => {
-                    create_runtime(None)
-                        .block_on(self.cloud_setup_run_cleanup(args, running_in_release))?;
+                Command::CloudSetupRunCleanup(mut args) => {
+                    self.config.apply_run_defaults(&mut args);
+                    if args.dry_run {
+                        self.dry_run(
+                            &args,
+                            Some("Cloud resources would be created for all of these benches before the first bench is run and cleaned up after the last bench is run."),
+                        )?
+                    } else {
+                        create_runtime(None)
+                            .block_on(self.cloud_setup_run_cleanup(args, running_in_release))?;
+                    }
                 }
-                Command::LocalRun(args) => {
-                    create_runtime(None).block_on(self.local_run(args, running_in_release))?;
+                Command::LocalRun(mut args) => {
+                    self.config.apply_run_defaults(&mut args);
+                    if args.dry_run {
+                        self.dry_run(&args, None)?
+                    } else {
+                        create_runtime(None).block_on(self.local_run(args, running_in_release))?;
+                    }
                 }
                 Command::InternalRun(args) => self.internal_run(&args, running_in_release)?,
             }
         } else if args.nextest_list() {
             list::nextest_list(&args, &self.benches);
         } else if let Some(name) = args.nextest_run_by_name() {
+            // nextest may run multiple benches in parallel, so wait our turn instead of failing
+            let _lock = WriteLock::acquire_blocking()?;
             create_runtime(None).block_on(self.run_nextest(name, running_in_release))?;
         } else if let Some(err) = args.nextest_invalid_args() {
             return Err(err);
@@ -128,8 +333,147 @@ impl<ResourcesRequired: Clone, Resources: Clone> Windsock<ResourcesRequired, Res
         Ok(())
     }
 
+    /// Checks the filter against the tags of the registered benches, and also the benches in the last run unless `requires_match` is set.
+    /// Warns about any tag keys or values that no bench has, or returns an error instead if `requires_match` is set and no bench matches the filter.
+    fn validate_filter(&self, query: &str, requires_match: bool) -> Result<()> {
+        let filter = Filter::from_query(query)
+            .with_context(|| format!("Failed to parse tag filter from {:?}", query))?;
+        let mut tags: Vec<Tags> = self.benches.iter().map(|x| x.tags.clone()).collect();
+        if !requires_match {
+            // Results can have tags that no registered bench has, such as the rate of a sweep of --operations-per-second
+            tags.extend(
+                ReportArchive::reports_in_last_run()
+                    .iter()
+                    .filter_map(|name| Tags::from_name(name).ok()),
+            );
+        }
+        let tags: Vec<&Tags> = tags.iter().collect();
+        let problems = filter.problems(&tags);
+        if requires_match && !tags.iter().any(|x| filter.matches(x)) {
+            let mut message = "No benches found with the specified filter".to_owned();
+            for problem in problems {
+                message.push('\n');
+                message.push_str(&problem);
+            }
+            return Err(anyhow!(message));
+        }
+        for problem in problems {
+            eprintln!("{}", style(format!("Warning: {problem}")).yellow());
+        }
+        Ok(())
+    }
+
+    /// Prints what a run with the provided `RunArgs` would do without running anything.
+    /// `cloud_resources` describes how the cloud resources would be acquired, or is None for a local run.
+    fn dry_run(&mut self, args: &RunArgs, cloud_resources: Option<&'static str>) -> Result<()> {
+        let filter = Filter::from_query(&args.filter())
+            .map_err(|err| anyhow!("Failed to parse FILTER {:?}\n{err}", args.filter()))?;
+
+        let mut plan = Plan {
+            cloud_resources,
+            ..Plan::default()
+        };
+        let mut names = vec![];
+        for bench in &self.benches {
+            let name = bench.tags.get_name();
+            if !filter.matches(&bench.tags) {
+                plan.unmatched += 1;
+                continue;
+            }
+            let unsupported: Vec<&str> = args
+                .profilers
+                .iter()
+                .filter(|x| !bench.supported_profilers.contains(x))
+                .map(|x| x.as_str())
+                .collect();
+            if !unsupported.is_empty() {
+                plan.skipped.push((
+                    name,
+                    format!("does not support the profilers: {}", unsupported.join(", ")),
+                ));
+            } else {
+                names.push(name);
+            }
+        }
+
+        if cloud_resources.is_some() {
+            // Cloud runs are ordered by the cloud according to the resources each bench requires
+            let bench_infos = self
+                .benches
+                .iter()
+                .filter(|x| names.contains(&x.tags.get_name()))
+                .map(|x| BenchInfo {
+                    name: x.tags.get_name(),
+                    resources: x.required_cloud_resources(),
+                })
+                .collect();
+            for bench_info in self.cloud.order_benches(bench_infos) {
+                let resources = self.cloud.describe_resources(&bench_info.resources);
+                self.plan_bench(&mut plan, args, &bench_info.name, resources);
+            }
+        } else {
+            let seed = args.seed.unwrap_or_else(random_seed);
+            plan.order = args.order.map(|order| describe_order(order, seed));
+            for (i, run_args) in self.local_runs(args, &filter, seed) {
+                self.plan_run(&mut plan, args, &self.benches[i], &run_args, None);
+            }
+        }
+
+        plan.print();
+        Ok(())
+    }
+
+    /// Adds every run of the named bench to the plan
+    fn plan_bench(&self, plan: &mut Plan, args: &RunArgs, name: &str, resources: Option<String>) {
+        let bench = self
+            .benches
+            .iter()
+            .find(|x| x.tags.get_name() == name)
+            .expect("Planned benches come from the registered benches");
+        for run_args in self.config.bench_run_args(args, &bench.tags).split_runs() {
+            self.plan_run(plan, args, bench, &run_args, resources.clone());
+        }
+    }
+
+    /// Adds a single run of a bench to the plan, as split by [`RunArgs::split_runs`]
+    fn plan_run(
+        &self,
+        plan: &mut Plan,
+        args: &RunArgs,
+        bench: &BenchState<ResourcesRequired, Resources>,
+        run_args: &RunArgs,
+        resources: Option<String>,
+    ) {
+        let name = bench.run_tags(run_args).get_name();
+        if args.resume && completed_in_last_run(&name) {
+            plan.skipped
+                .push((name, "already has results in the last run".to_owned()));
+        } else {
+            let find_capacity = run_args.capacity.find_capacity;
+            let parameters = BenchParameters::from_args(run_args);
+            plan.run.push(PlannedBench {
+                name,
+                bench_length_seconds: parameters.runtime_seconds,
+                warmup_seconds: parameters.warmup_seconds,
+                operations_per_second: if find_capacity {
+                    Some(
+                        parameters
+                            .operations_per_second
+                            .unwrap_or(DEFAULT_START_RATE),
+                    )
+                } else {
+                    parameters.operations_per_second
+                },
+                load_profile: Some(parameters.load_profile).filter(|profile| profile.is_varying()),
+                find_capacity,
+                bencher_cpus: bench.bencher_cpus(run_args),
+                resources,
+            });
+        }
+    }
+
     async fn cloud_run(&mut self, args: RunArgs, running_in_release: bool) -> Result<()> {
-        let bench_infos = self.bench_infos(&args.filter(), &args.profilers)?;
+        let bench_infos = self.run_bench_infos(&args)?;
         let resources = self.load_cloud_from_disk(&bench_infos).await?;
         self.run_filtered_benches_cloud(args, running_in_release, bench_infos, resources)
             .await?;
@@ -143,7 +487,7 @@ impl<ResourcesRequired: Clone, Resources: Clone> Windsock<ResourcesRequired, Res
         args: RunArgs,
         running_in_release: bool,
     ) -> Result<()> {
-        let bench_infos = self.bench_infos(&args.filter(), &args.profilers)?;
+        let bench_infos = self.run_bench_infos(&args)?;
         let resources = self.temp_setup_cloud(&bench_infos).await?;
         self.run_filtered_benches_cloud(args, running_in_release, bench_infos, resources)
             .await?;
@@ -166,10 +510,19 @@ impl<ResourcesRequired: Clone, Resources: Clone> Windsock<ResourcesRequired, Res
                     .iter()
                     .all(|x| bench.supported_profilers.contains(x))
                 {
-                    create_runtime(bench.cores_required()).block_on(async {
-                        bench.run(args, running_in_release, resources).await;
-                    });
-                    Ok(())
+                    // A bencher without its own cores would otherwise inherit the pinning of --orchestrator-cpus
+                    // while its results record it as not pinned.
+                    match bench.bencher_cpus(args) {
+                        Some(cpus) => cpus.pin_current_thread()?,
+                        None => affinity::unpin_current_thread().context(
+                            "Failed to undo the pinning inherited from the orchestrator",
+                        )?,
+                    }
+                    create_runtime(bench.cores_required()).block_on(bench.run(
+                        args,
+                        running_in_release,
+                        resources,
+                    ))
                 } else {
                     Err(anyhow!(
                         "Specified bench {name:?} was requested to run with the profilers {:?} but it only supports the profilers {:?}",
@@ -188,7 +541,22 @@ impl<ResourcesRequired: Clone, Resources: Clone> Windsock<ResourcesRequired, Res
             // This is not a real bench we are just testing that it works,
             // so set some really minimal runtime values
             bench_length_seconds: Some(2),
-            operations_per_second: Some(100),
+            warmup_seconds: None,
+            operations_per_second: Some(OperationsPerSecond::Rate(100)),
+            load_profile: None,
+            resume: false,
+            append: false,
+            junit: None,
+            keep_going: false,
+            timeout_seconds: None,
+            repeat: None,
+            order: None,
+            seed: None,
+            bencher_cpus: None,
+            orchestrator_cpus: None,
+            repetition: None,
+            capacity: CapacityArgs::default(),
+            dry_run: false,
             filter: Some(name.to_string()),
         };
 
@@ -218,6 +586,35 @@ impl<ResourcesRequired: Clone, Resources: Clone> Windsock<ResourcesRequired, Res
         Ok(self.cloud.order_benches(bench_infos))
     }
 
+    /// The `bench_infos` of the benches that will actually be run by the provided `RunArgs`
+    fn run_bench_infos(&mut self, args: &RunArgs) -> Result<Vec<BenchInfo<ResourcesRequired>>> {
+        let mut bench_infos = self.bench_infos(&args.filter(), &args.profilers)?;
+        if args.resume {
+            bench_infos.retain(|x| {
+                // Benches run with a sweep are only skipped once every rate has results
+                let bench = self
+                    .benches
+                    .iter()
+                    .find(|bench| bench.tags.get_name() == x.name)
+                    .unwrap();
+                let completed = self
+                    .config
+                    .bench_run_args(args, &bench.tags)
+                    .split_runs()
+                    .iter()
+                    .all(|run_args| completed_in_last_run(&bench.run_tags(run_args).get_name()));
+                if completed {
+                    println!(
+                        "Skipping {:?} as it already has results in the last run",
+                        x.name
+                    );
+                }
+                !completed
+            });
+        }
+        Ok(bench_infos)
+    }
+
     async fn load_cloud_from_disk(
         &mut self,
         bench_infos: &[BenchInfo<ResourcesRequired>],
@@ -279,23 +676,66 @@ impl<ResourcesRequired: Clone, Resources: Clone> Windsock<ResourcesRequired, Res
         bench_infos: Vec<BenchInfo<ResourcesRequired>>,
         mut resources: Resources,
     ) -> Result<()> {
-        ReportArchive::clear_last_run();
+        if !args.resume && !args.append {
+            ReportArchive::clear_last_run();
+        }
 
+        let mut outcomes = RunOutcomes::default();
+        let mut failures = vec![];
         for (i, bench_info) in bench_infos.iter().enumerate() {
             for bench in &mut self.benches {
                 if bench.tags.get_name() == bench_info.name {
+                    let runs = self.config.bench_run_args(&args, &bench.tags).split_runs();
+                    if !failures.is_empty() && !args.keep_going {
+                        for run_args in &runs {
+                            outcomes.push(
+                                bench.run_tags(run_args).get_name(),
+                                Outcome::Skipped {
+                                    reason: SKIPPED_AFTER_FAILURE,
+                                },
+                            );
+                        }
+                        break;
+                    }
                     self.cloud
                         .adjust_resources(&bench_infos, i, &mut resources)
                         .await;
-                    bench
-                        .orchestrate(&args, running_in_release, Some(resources.clone()))
-                        .await;
+                    for run_args in runs {
+                        let name = bench.run_tags(&run_args).get_name();
+                        if args.resume && completed_in_last_run(&name) {
+                            println!("Skipping {name:?} as it already has results in the last run");
+                            outcomes.push(
+                                name,
+                                Outcome::Skipped {
+                                    reason: SKIPPED_COMPLETED,
+                                },
+                            );
+                        } else if !failures.is_empty() && !args.keep_going {
+                            outcomes.push(
+                                name,
+                                Outcome::Skipped {
+                                    reason: SKIPPED_AFTER_FAILURE,
+                                },
+                            );
+                        } else {
+                            let start = Instant::now();
+                            let result = bench
+                                .orchestrate(&run_args, running_in_release, Some(resources.clone()))
+                                .await;
+                            if let Some(err) = record_outcome(&mut outcomes, name, start, result) {
+                                if args.keep_going {
+                                    println!("{err:?}\nContinuing with the remaining benches");
+                                }
+                                failures.push(err);
+                            }
+                        }
+                    }
                     break;
                 }
             }
         }
 
-        Ok(())
+        finish_run(&outcomes, &args, "cloud-run", failures)
     }
 
     async fn cloud_cleanup(&mut self) {
@@ -303,23 +743,172 @@ impl<ResourcesRequired: Clone, Resources: Clone> Windsock<ResourcesRequired, Res
         self.cloud.cleanup_resources().await;
     }
 
+    /// Every run of the benches that match the filter and support the profilers, in the order specified by `--order`.
+    /// Each run is paired with the index of the bench it runs.
+    fn local_runs(&self, args: &RunArgs, filter: &Filter, seed: u64) -> Vec<(usize, RunArgs)> {
+        let runs = self
+            .benches
+            .iter()
+            .enumerate()
+            .filter(|(_, bench)| {
+                filter.matches(&bench.tags)
+                    && args
+                        .profilers
+                        .iter()
+                        .all(|x| bench.supported_profilers.contains(x))
+            })
+            .flat_map(|(i, bench)| {
+                self.config
+                    .bench_run_args(args, &bench.tags)
+                    .split_runs()
+                    .into_iter()
+                    .map(move |run_args| (i, run_args))
+            })
+            .collect();
+        order_runs(runs, args.order, seed)
+    }
+
     async fn local_run(&mut self, args: RunArgs, running_in_release: bool) -> Result<()> {
-        ReportArchive::clear_last_run();
+        if !args.resume && !args.append {
+            ReportArchive::clear_last_run();
+        }
         let filter = args.filter();
         let filter = Filter::from_query(&filter)
             .map_err(|err| anyhow!("Failed to parse FILTER {:?}\n{err}", filter))?;
 
-        for bench in &mut self.benches {
-            if filter.matches(&bench.tags)
-                && args
-                    .profilers
-                    .iter()
-                    .all(|x| bench.supported_profilers.contains(x))
-            {
-                bench.orchestrate(&args, running_in_release, None).await;
+        let seed = args.seed.unwrap_or_else(random_seed);
+        let runs = self.local_runs(&args, &filter, seed);
+        let order = args.order.map(|order| describe_order(order, seed));
+        if let Some(order) = &order {
+            println!("Running {} benches in {order}", runs.len());
+        }
+
+        let mut outcomes = RunOutcomes::default();
+        let mut failures = vec![];
+        let total = runs.len();
+        for (position, (i, run_args)) in runs.into_iter().enumerate() {
+            let bench = &mut self.benches[i];
+            let name = bench.run_tags(&run_args).get_name();
+            if args.resume && completed_in_last_run(&name) {
+                println!("Skipping {name:?} as it already has results in the last run");
+                outcomes.push(
+                    name,
+                    Outcome::Skipped {
+                        reason: SKIPPED_COMPLETED,
+                    },
+                );
+            } else if !failures.is_empty() && !args.keep_going {
+                outcomes.push(
+                    name,
+                    Outcome::Skipped {
+                        reason: SKIPPED_AFTER_FAILURE,
+                    },
+                );
+            } else {
+                let start = Instant::now();
+                let mut result = bench.orchestrate(&run_args, running_in_release, None).await;
+                if let (Ok(()), Some(order)) = (&result, &order) {
+                    // record the position of the run so that drift can be accounted for when reading the results
+                    result = ReportArchive::add_info_message(
+                        &name,
+                        format!("Run {} of {total} in {order}", position + 1),
+                    );
+                }
+                if let Some(err) = record_outcome(&mut outcomes, name, start, result) {
+                    if args.keep_going {
+                        println!("{err:?}\nContinuing with the remaining benches");
+                    }
+                    failures.push(err);
+                }
             }
         }
+
+        finish_run(&outcomes, &args, "local-run", failures)
+    }
+}
+
+const SKIPPED_AFTER_FAILURE: &str =
+    "Not run because an earlier bench failed, use --keep-going to run it anyway";
+const SKIPPED_COMPLETED: &str = "Already has results in the last run";
+
+/// Prints a summary of the run if any bench failed, writes the JUnit report if requested
+/// and returns an error if any bench failed.
+fn finish_run(
+    outcomes: &RunOutcomes,
+    args: &RunArgs,
+    suite_name: &str,
+    mut failures: Vec<anyhow::Error>,
+) -> Result<()> {
+    if args.keep_going || !failures.is_empty() {
+        outcomes.print_summary();
+    }
+    if let Some(path) = &args.junit {
+        outcomes.write(path, suite_name)?;
+    }
+    if failures.is_empty() {
         Ok(())
+    } else if args.keep_going {
+        Err(anyhow!(
+            "{} of {} benches failed, their errors are displayed above",
+            failures.len(),
+            outcomes.len()
+        ))
+    } else {
+        Err(failures.remove(0))
+    }
+}
+
+/// Records the outcome of a bench that was run, returning the error if the bench failed.
+/// Once a bench fails the remaining benches are skipped, unless `--keep-going` is specified.
+fn record_outcome(
+    outcomes: &mut RunOutcomes,
+    name: String,
+    start: Instant,
+    result: Result<()>,
+) -> Option<anyhow::Error> {
+    let time = start.elapsed();
+    match result {
+        Ok(()) => {
+            outcomes.push(name, Outcome::Completed { time });
+            None
+        }
+        Err(err) => {
+            let err = err.context(format!("Bench {name:?} failed"));
+            let outcome = match err.downcast_ref::<TimedOut>() {
+                Some(timed_out) => Outcome::TimedOut {
+                    time,
+                    limit: timed_out.limit,
+                },
+                None => Outcome::Failed {
+                    time,
+                    reason: err
+                        .root_cause()
+                        .to_string()
+                        .lines()
+                        .next()
+                        .unwrap_or_default()
+                        .to_owned(),
+                    error: format!("{err:?}"),
+                },
+            };
+            outcomes.push(name, outcome);
+            Some(err)
+        }
+    }
+}
+
+/// Returns true if the bench has a valid archive in the last run
+fn completed_in_last_run(name: &str) -> bool {
+    ReportArchive::load(name).is_ok()
+}
+
+/// Pins windsock to `--orchestrator-cpus`, this must be called before the runtime is created so that its threads inherit the pinning
+fn pin_orchestrator(args: &RunArgs) -> Result<()> {
+    match &args.orchestrator_cpus {
+        Some(cpus) if !args.dry_run => cpus
+            .pin_current_thread()
+            .context("Failed to apply --orchestrator-cpus"),
+        _ => Ok(()),
     }
 }
 
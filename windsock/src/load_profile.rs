@@ -0,0 +1,384 @@
+use crate::units::parse_duration;
+use anyhow::{Error, Result, anyhow};
+use std::{
+    f64::consts::PI,
+    fmt::Display,
+    str::FromStr,
+    sync::atomic::{AtomicBool, Ordering},
+    time::Duration,
+};
+
+/// Set once [`LoadProfile::rate_at`] is called on a varying profile, so that a bench ignoring `--load-profile` can be detected
+static VARYING_PROFILE_FOLLOWED: AtomicBool = AtomicBool::new(false);
+
+/// Returns true if a varying profile has been followed by anything in this process, see [`LoadProfile::rate_at`]
+pub(crate) fn varying_profile_followed() -> bool {
+    VARYING_PROFILE_FOLLOWED.load(Ordering::Relaxed)
+}
+
+/// Describes how the target operations per second of a bench changes over the course of the bench.
+///
+/// Times are measured from when the bench begins.
+/// Benches using [`crate::BenchTask::spawn_tasks`] follow the profile automatically,
+/// other benches can call [`LoadProfile::rate_at`] to find the rate they should currently be running at.
+#[derive(Clone, Debug, PartialEq)]
+pub enum LoadProfile {
+    /// Run as many operations per second as possible
+    Unlimited,
+    /// Run at the same rate for the whole bench
+    Constant(u64),
+    /// Increase linearly from `from` to `to` over `over` and then remain at `to`
+    Ramp { from: u64, to: u64, over: Duration },
+    /// Start at `from` and increase by `by` every `every` until `to` is reached
+    Step {
+        from: u64,
+        to: u64,
+        by: u64,
+        every: Duration,
+    },
+    /// Oscillate between `low` and `high`, starting at `low` and reaching `high` halfway through each period
+    Sine {
+        low: u64,
+        high: u64,
+        period: Duration,
+    },
+    /// Run at `base` except for a spike to `peak` beginning at `at` and lasting for `lasting`
+    Spike {
+        base: u64,
+        peak: u64,
+        at: Duration,
+        lasting: Duration,
+    },
+}
+
+impl LoadProfile {
+    /// The target operations per second at `elapsed` into the bench, None if unlimited.
+    /// Calling this on a varying profile marks the profile as followed, a run of a varying profile fails if it is never followed.
+    pub fn rate_at(&self, elapsed: Duration) -> Option<u64> {
+        if self.is_varying() {
+            VARYING_PROFILE_FOLLOWED.store(true, Ordering::Relaxed);
+        }
+        match self {
+            LoadProfile::Unlimited => None,
+            LoadProfile::Constant(rate) => Some(*rate),
+            LoadProfile::Ramp { from, to, over } => {
+                let progress = if over.is_zero() {
+                    1.0
+                } else {
+                    (elapsed.as_secs_f64() / over.as_secs_f64()).min(1.0)
+                };
+                Some(interpolate(*from, *to, progress))
+            }
+            LoadProfile::Step {
+                from,
+                to,
+                by,
+                every,
+            } => {
+                let steps = if every.is_zero() {
+                    u64::MAX
+                } else {
+                    (elapsed.as_secs_f64() / every.as_secs_f64()) as u64
+                };
+                Some(from.saturating_add(by.saturating_mul(steps)).min(*to))
+            }
+            LoadProfile::Sine { low, high, period } => {
+                let phase = if period.is_zero() {
+                    0.0
+                } else {
+                    elapsed.as_secs_f64() / period.as_secs_f64()
+                };
+                Some(interpolate(
+                    *low,
+                    *high,
+                    (1.0 - (2.0 * PI * phase).cos()) / 2.0,
+                ))
+            }
+            LoadProfile::Spike {
+                base,
+                peak,
+                at,
+                lasting,
+            } => {
+                if elapsed >= *at && elapsed < *at + *lasting {
+                    Some(*peak)
+                } else {
+                    Some(*base)
+                }
+            }
+        }
+    }
+
+    /// The highest target operations per second reached at any point in the profile, None if unlimited
+    pub fn peak(&self) -> Option<u64> {
+        match self {
+            LoadProfile::Unlimited => None,
+            LoadProfile::Constant(rate) => Some(*rate),
+            LoadProfile::Ramp { from, to, .. } => Some(*from.max(to)),
+            LoadProfile::Step { from, to, .. } => Some(*from.max(to)),
+            LoadProfile::Sine { low, high, .. } => Some(*low.max(high)),
+            LoadProfile::Spike { base, peak, .. } => Some(*base.max(peak)),
+        }
+    }
+
+    /// Returns true if the target rate changes over the course of the bench
+    pub fn is_varying(&self) -> bool {
+        !matches!(self, LoadProfile::Unlimited | LoadProfile::Constant(_))
+    }
+
+    /// The mean target operations per second of each second of a bench of the specified length.
+    /// Empty if unlimited.
+    pub(crate) fn target_each_second(&self, seconds: usize) -> Vec<u64> {
+        const SAMPLES: u32 = 10;
+        (0..seconds)
+            .map_while(|second| {
+                let total = (0..SAMPLES)
+                    .map(|sample| {
+                        let offset = (sample as f64 + 0.5) / SAMPLES as f64;
+                        self.rate_at(Duration::from_secs_f64(second as f64 + offset))
+                    })
+                    .sum::<Option<u64>>()?;
+                Some(total / SAMPLES as u64)
+            })
+            .collect()
+    }
+}
+
+impl From<Option<u64>> for LoadProfile {
+    fn from(operations_per_second: Option<u64>) -> Self {
+        match operations_per_second {
+            Some(rate) => LoadProfile::Constant(rate),
+            None => LoadProfile::Unlimited,
+        }
+    }
+}
+
+fn interpolate(from: u64, to: u64, progress: f64) -> u64 {
+    (from as f64 + (to as f64 - from as f64) * progress).round() as u64
+}
+
+const HELP: &str = "Valid load profiles are:
+* 1000 - a constant rate
+* ramp 1000..10000 over 60s - increase linearly and then remain at the final rate
+* step 1000..10000 by 1000 every 10s - increase in steps until the final rate is reached
+* sine 1000..10000 period 30s - oscillate between the two rates
+* spike 1000..10000 at 20s for 5s - run at the first rate except for a spike to the second rate";
+
+impl FromStr for LoadProfile {
+    type Err = Error;
+
+    fn from_str(text: &str) -> Result<Self> {
+        let invalid = || anyhow!("Invalid load profile {text:?}\n{HELP}");
+        let parse_rate = |rate: &str| rate.parse::<u64>().map_err(|_| invalid());
+        let parse_range = |range: &str| -> Result<(u64, u64)> {
+            let (from, to) = range.split_once("..").ok_or_else(invalid)?;
+            Ok((parse_rate(from)?, parse_rate(to)?))
+        };
+        let parse_time = |time: &str| {
+            parse_duration(time).map_err(|e| anyhow!("Invalid load profile {text:?}, {e}"))
+        };
+
+        let words: Vec<&str> = text.split_whitespace().collect();
+        match words.as_slice() {
+            ["unlimited"] => Ok(LoadProfile::Unlimited),
+            [rate] => Ok(LoadProfile::Constant(parse_rate(rate)?)),
+            ["ramp", range, "over", over] => {
+                let (from, to) = parse_range(range)?;
+                Ok(LoadProfile::Ramp {
+                    from,
+                    to,
+                    over: parse_time(over)?,
+                })
+            }
+            ["step", range, "by", by, "every", every] => {
+                let (from, to) = parse_range(range)?;
+                let by = parse_rate(by)?;
+                if by == 0 || from > to {
+                    return Err(anyhow!(
+                        "Invalid load profile {text:?}, the step must be positive and the start must not be after the end"
+                    ));
+                }
+                Ok(LoadProfile::Step {
+                    from,
+                    to,
+                    by,
+                    every: parse_time(every)?,
+                })
+            }
+            ["sine", range, "period", period] => {
+                let (low, high) = parse_range(range)?;
+                Ok(LoadProfile::Sine {
+                    low,
+                    high,
+                    period: parse_time(period)?,
+                })
+            }
+            ["spike", range, "at", at, "for", lasting] => {
+                let (base, peak) = parse_range(range)?;
+                Ok(LoadProfile::Spike {
+                    base,
+                    peak,
+                    at: parse_time(at)?,
+                    lasting: parse_time(lasting)?,
+                })
+            }
+            _ => Err(invalid()),
+        }
+    }
+}
+
+/// Displays the profile in the same form it is parsed from, so that it can be passed on to `internal-run`
+impl Display for LoadProfile {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LoadProfile::Unlimited => write!(f, "unlimited"),
+            LoadProfile::Constant(rate) => write!(f, "{rate}"),
+            LoadProfile::Ramp { from, to, over } => {
+                write!(f, "ramp {from}..{to} over {}", DisplayTime(*over))
+            }
+            LoadProfile::Step {
+                from,
+                to,
+                by,
+                every,
+            } => write!(f, "step {from}..{to} by {by} every {}", DisplayTime(*every)),
+            LoadProfile::Sine { low, high, period } => {
+                write!(f, "sine {low}..{high} period {}", DisplayTime(*period))
+            }
+            LoadProfile::Spike {
+                base,
+                peak,
+                at,
+                lasting,
+            } => write!(
+                f,
+                "spike {base}..{peak} at {} for {}",
+                DisplayTime(*at),
+                DisplayTime(*lasting)
+            ),
+        }
+    }
+}
+
+/// Displays a duration without losing precision, in the largest unit that can represent it exactly
+struct DisplayTime(Duration);
+
+impl Display for DisplayTime {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let nanos = self.0.as_nanos();
+        if nanos % 1_000_000_000 == 0 {
+            write!(f, "{}s", nanos / 1_000_000_000)
+        } else if nanos % 1_000_000 == 0 {
+            write!(f, "{}ms", nanos / 1_000_000)
+        } else {
+            write!(f, "{nanos}ns")
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_and_display() {
+        for (text, profile) in [
+            ("unlimited", LoadProfile::Unlimited),
+            ("1000", LoadProfile::Constant(1000)),
+            (
+                "ramp 1000..10000 over 60s",
+                LoadProfile::Ramp {
+                    from: 1000,
+                    to: 10000,
+                    over: Duration::from_secs(60),
+                },
+            ),
+            (
+                "step 1000..10000 by 1000 every 10s",
+                LoadProfile::Step {
+                    from: 1000,
+                    to: 10000,
+                    by: 1000,
+                    every: Duration::from_secs(10),
+                },
+            ),
+            (
+                "sine 1000..10000 period 500ms",
+                LoadProfile::Sine {
+                    low: 1000,
+                    high: 10000,
+                    period: Duration::from_millis(500),
+                },
+            ),
+            (
+                "spike 1000..10000 at 20s for 1500ns",
+                LoadProfile::Spike {
+                    base: 1000,
+                    peak: 10000,
+                    at: Duration::from_secs(20),
+                    lasting: Duration::from_nanos(1500),
+                },
+            ),
+        ] {
+            assert_eq!(text.parse::<LoadProfile>().unwrap(), profile, "{text:?}");
+            assert_eq!(profile.to_string(), text);
+        }
+    }
+
+    #[test]
+    fn parse_displays_fractional_times_exactly() {
+        let profile: LoadProfile = "ramp 0..10 over 1.5s".parse().unwrap();
+        assert_eq!(profile.to_string(), "ramp 0..10 over 1500ms");
+        assert_eq!(profile.to_string().parse::<LoadProfile>().unwrap(), profile);
+    }
+
+    #[test]
+    fn parse_errors() {
+        for text in [
+            "",
+            "fast",
+            "-1",
+            "ramp 1000..10000",
+            "ramp 1000-10000 over 60s",
+            "ramp 1000..ten over 60s",
+            "ramp 1000..10000 over 60",
+            "step 1000..10000 by 0 every 10s",
+            "step 10000..1000 by 1000 every 10s",
+            "sine 1000..10000 every 30s",
+            "spike 1000..10000 at 20s",
+        ] {
+            assert!(text.parse::<LoadProfile>().is_err(), "{text:?}");
+        }
+    }
+
+    #[test]
+    fn rate_at() {
+        let ramp: LoadProfile = "ramp 1000..2000 over 10s".parse().unwrap();
+        assert_eq!(ramp.rate_at(Duration::ZERO), Some(1000));
+        assert_eq!(ramp.rate_at(Duration::from_secs(5)), Some(1500));
+        assert_eq!(ramp.rate_at(Duration::from_secs(20)), Some(2000));
+
+        let step: LoadProfile = "step 1000..2500 by 1000 every 10s".parse().unwrap();
+        assert_eq!(step.rate_at(Duration::from_secs(9)), Some(1000));
+        assert_eq!(step.rate_at(Duration::from_secs(10)), Some(2000));
+        assert_eq!(step.rate_at(Duration::from_secs(30)), Some(2500));
+
+        let sine: LoadProfile = "sine 1000..2000 period 10s".parse().unwrap();
+        assert_eq!(sine.rate_at(Duration::ZERO), Some(1000));
+        assert_eq!(sine.rate_at(Duration::from_secs(5)), Some(2000));
+
+        let spike: LoadProfile = "spike 1000..5000 at 2s for 1s".parse().unwrap();
+        assert_eq!(spike.rate_at(Duration::from_millis(1999)), Some(1000));
+        assert_eq!(spike.rate_at(Duration::from_secs(2)), Some(5000));
+        assert_eq!(spike.rate_at(Duration::from_secs(3)), Some(1000));
+
+        assert_eq!(LoadProfile::Unlimited.rate_at(Duration::ZERO), None);
+    }
+
+    #[test]
+    fn target_each_second() {
+        let spike: LoadProfile = "spike 1000..5000 at 1s for 1s".parse().unwrap();
+        assert_eq!(spike.target_each_second(3), vec![1000, 5000, 1000]);
+        assert!(LoadProfile::Unlimited.target_each_second(3).is_empty());
+    }
+}
@@ -0,0 +1,55 @@
+use crate::cli::{RunArgs, RunOrder};
+use std::hash::{BuildHasher, RandomState};
+
+/// Chooses the seed for `--order random` when `--seed` is not specified
+pub(crate) fn random_seed() -> u64 {
+    RandomState::new().hash_one(0)
+}
+
+/// Describes the order runs are run in, including everything needed to reproduce it
+pub(crate) fn describe_order(order: RunOrder, seed: u64) -> String {
+    match order {
+        RunOrder::Random => format!("random order with --seed {seed}"),
+        RunOrder::Interleave => "interleaved order".to_owned(),
+    }
+}
+
+/// Orders the runs of benches according to `--order`, each run is paired with the bench it runs.
+/// Runs are left in the order provided when no order is specified.
+pub(crate) fn order_runs<T>(
+    mut runs: Vec<(T, RunArgs)>,
+    order: Option<RunOrder>,
+    seed: u64,
+) -> Vec<(T, RunArgs)> {
+    match order {
+        None => {}
+        Some(RunOrder::Random) => shuffle(&mut runs, seed),
+        Some(RunOrder::Interleave) => {
+            // A round for each repetition, the sort is stable so the runs within a round keep their order
+            runs.sort_by_key(|(_, args)| args.repetition);
+            for (i, round) in runs
+                .chunk_by_mut(|a, b| a.1.repetition == b.1.repetition)
+                .enumerate()
+            {
+                // Reversing every second round places each bench at the start of a round as often as at the end
+                if i % 2 == 1 {
+                    round.reverse();
+                }
+            }
+        }
+    }
+    runs
+}
+
+/// A Fisher-Yates shuffle driven by SplitMix64, so that a seed produces the same order on every platform and version of windsock
+fn shuffle<T>(items: &mut [T], seed: u64) {
+    let mut state = seed;
+    for i in (1..items.len()).rev() {
+        state = state.wrapping_add(0x9E3779B97F4A7C15);
+        let mut z = state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+        z ^= z >> 31;
+        items.swap(i, (z % (i as u64 + 1)) as usize);
+    }
+}
@@ -0,0 +1,112 @@
+use crate::affinity::CpuSet;
+use crate::load_profile::LoadProfile;
+use crate::units::format_seconds;
+
+/// What a run would do, displayed by `--dry-run` instead of running any benches
+#[derive(Default)]
+pub(crate) struct Plan {
+    /// The benches that would be run, in the order they would be run
+    pub(crate) run: Vec<PlannedBench>,
+    /// The benches that match the filter but would not be run, along with the reason why
+    pub(crate) skipped: Vec<(String, String)>,
+    /// The number of benches that do not match the filter
+    pub(crate) unmatched: usize,
+    /// The `--order` the benches would be run in, None for the order they are defined in
+    pub(crate) order: Option<String>,
+    /// How the cloud resources would be acquired, None for local runs
+    pub(crate) cloud_resources: Option<&'static str>,
+}
+
+pub(crate) struct PlannedBench {
+    pub(crate) name: String,
+    pub(crate) bench_length_seconds: u32,
+    pub(crate) warmup_seconds: u32,
+    pub(crate) operations_per_second: Option<u64>,
+    /// The `--load-profile` the bench follows instead of a constant rate
+    pub(crate) load_profile: Option<LoadProfile>,
+    /// The bench is run once for each rate probed by `--find-capacity`, starting from `operations_per_second`
+    pub(crate) find_capacity: bool,
+    /// The cores the bencher would be pinned to
+    pub(crate) bencher_cpus: Option<CpuSet>,
+    /// The description of the cloud resources required by the bench, as provided by `Cloud::describe_resources`
+    pub(crate) resources: Option<String>,
+}
+
+impl Plan {
+    pub(crate) fn print(&self) {
+        println!("Dry run, no benches will be run or cloud resources created.");
+
+        println!();
+        if self.run.is_empty() {
+            println!("No benches would be run.");
+        } else {
+            match &self.order {
+                Some(order) => println!("Benches that would be run, in {order}:"),
+                None => println!("Benches that would be run, in order:"),
+            }
+            let index_width = self.run.len().to_string().len();
+            for (i, bench) in self.run.iter().enumerate() {
+                let mut length = format_seconds(bench.bench_length_seconds as u64);
+                if bench.warmup_seconds > 0 {
+                    length = format!(
+                        "{length} after {} warmup",
+                        format_seconds(bench.warmup_seconds as u64)
+                    );
+                }
+                let description = match (
+                    &bench.load_profile,
+                    bench.operations_per_second,
+                    bench.find_capacity,
+                ) {
+                    (Some(profile), _, _) => format!("{length}, load profile `{profile}`"),
+                    (None, Some(ops), true) => format!(
+                        "{length} per probe, capacity search starting from {ops} operations per second"
+                    ),
+                    (None, Some(ops), false) => format!("{length}, {ops} operations per second"),
+                    (None, None, _) => format!("{length}, unlimited operations per second"),
+                };
+                let description = match &bench.bencher_cpus {
+                    Some(cpus) => format!("{description}, bencher pinned to CPUs {cpus}"),
+                    None => description,
+                };
+                println!("{:>index_width$}. {} ({description})", i + 1, bench.name);
+                if let Some(resources) = &bench.resources {
+                    for line in resources.lines() {
+                        println!("{:index_width$}  {line}", "");
+                    }
+                }
+            }
+        }
+
+        if !self.skipped.is_empty() {
+            println!();
+            println!("Benches that would be skipped:");
+            for (name, reason) in &self.skipped {
+                println!("{name} - {reason}");
+            }
+        }
+        if self.unmatched > 0 {
+            println!();
+            println!("{} other benches do not match the filter.", self.unmatched);
+        }
+
+        if let Some(cloud_resources) = self.cloud_resources {
+            println!();
+            println!("{cloud_resources}");
+        }
+
+        if !self.run.is_empty() {
+            let total_seconds: u64 = self
+                .run
+                .iter()
+                .map(|x| x.bench_length_seconds as u64 + x.warmup_seconds as u64)
+                .sum();
+            println!();
+            println!(
+                "Estimated duration: at least {}, the total length of the benches and their warmups.",
+                format_seconds(total_seconds)
+            );
+            println!("Setting up and tearing down each bench will take additional time.");
+        }
+    }
+}
@@ -1,8 +1,20 @@
-use crate::{Goal, bench::Tags, data::windsock_path};
-use anyhow::{Result, anyhow};
+use crate::{
+    Goal,
+    affinity::CpuSet,
+    bench::{Tags, file_name_from_name, is_hashed_file_name},
+    data::{tmp_path, windsock_path, write_atomic},
+    load_profile::{LoadProfile, varying_profile_followed},
+    statistic::Measured,
+    units::Unit,
+};
+use anyhow::{Context, Result, anyhow};
 use bincode::config::Configuration;
 use serde::{Deserialize, Serialize};
-use std::{io::ErrorKind, path::PathBuf, time::Duration};
+use std::{
+    io::ErrorKind,
+    path::{Path, PathBuf},
+    time::Duration,
+};
 use strum::{EnumCount, EnumIter, IntoEnumIterator};
 use time::OffsetDateTime;
 use tokio::sync::mpsc::UnboundedReceiver;
@@ -75,7 +87,7 @@ pub struct ExternalReport {
     pub error_messages: Vec<String>,
 }
 
-#[derive(EnumIter, EnumCount)]
+#[derive(EnumIter, EnumCount, Clone, Copy, PartialEq, Debug)]
 pub enum Percentile {
     Min = 0,
     P1,
@@ -142,6 +154,8 @@ pub type Percentiles = [Duration; Percentile::COUNT];
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct ReportArchive {
     pub(crate) running_in_release: bool,
+    /// The cores the bencher was pinned to, None if it could run on any core
+    pub(crate) bencher_cpus: Option<CpuSet>,
     pub(crate) tags: Tags,
     pub bench_started_at: OffsetDateTime,
     pub(crate) operations_report: Option<OperationsReport>,
@@ -156,12 +170,18 @@ pub struct ReportArchive {
 pub struct OperationsReport {
     pub total: u64,
     pub total_errors: u64,
+    /// None if unlimited or if the rate varied according to a [`crate::LoadProfile`]
     pub requested_operations_per_second: Option<u64>,
     pub total_operations_per_second: u32,
     pub total_errors_per_second: u32,
     pub mean_time: Option<Duration>,
     pub time_percentiles: Percentiles,
     pub total_each_second: Vec<u64>,
+    /// The target operations per second of each second when the rate varied according to a [`crate::LoadProfile`], otherwise empty
+    pub requested_each_second: Vec<u64>,
+    /// The number of errors in each second, aligned with `total_each_second`
+    pub errors_each_second: Vec<u64>,
+    pub time_percentiles_each_second: Vec<Percentiles>,
 }
 
 /// The pubsub results of a benchmark run.
@@ -172,6 +192,7 @@ pub struct PubSubReport {
     pub total_consume: u64,
     pub total_consume_error: u64,
     pub total_backlog: i64,
+    /// None if unlimited or if the rate varied according to a [`crate::LoadProfile`]
     pub requested_produce_per_second: Option<u64>,
     pub produce_per_second: u32,
     pub produce_errors_per_second: u32,
@@ -182,22 +203,29 @@ pub struct PubSubReport {
     pub produce_mean_time: Option<Duration>,
     pub produce_time_percentiles: Percentiles,
     pub produce_each_second: Vec<u64>,
+    /// The target produce per second of each second when the rate varied according to a [`crate::LoadProfile`], otherwise empty
+    pub requested_produce_each_second: Vec<u64>,
     pub consume_each_second: Vec<u64>,
     pub backlog_each_second: Vec<i64>,
+    pub produce_time_percentiles_each_second: Vec<Percentiles>,
 }
 
 /// Extra metrics that can be inserted into bench results.
+///
+/// Values are stored unformatted along with their [`Unit`] so that windsock can format, scale and compare them consistently with its own results.
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub enum Metric {
     Total {
         name: String,
-        compare: f64,
-        value: String,
+        value: f64,
+        unit: Unit,
         goal: Goal,
     },
     EachSecond {
         name: String,
-        values: Vec<(f64, String, Goal)>,
+        values: Vec<f64>,
+        unit: Unit,
+        goal: Goal,
     },
     LatencyPercentiles {
         name: String,
@@ -209,17 +237,12 @@ pub enum Metric {
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct LatencyPercentile {
     pub quantile: String,
-    pub value: f64,
-    pub value_display: String,
+    pub value: Duration,
 }
 
 impl LatencyPercentile {
-    pub(crate) fn to_measurement(&self) -> (f64, String, Goal) {
-        (
-            self.value,
-            self.value_display.clone(),
-            Goal::SmallerIsBetter,
-        )
+    pub(crate) fn to_measurement(&self) -> Measured {
+        Measured::duration(self.value, Goal::SmallerIsBetter)
     }
 }
 
@@ -245,15 +268,6 @@ impl Metric {
             },
         }
     }
-
-    #[allow(clippy::len_without_is_empty)]
-    pub(crate) fn len(&self) -> usize {
-        match self {
-            Metric::Total { .. } => 1,
-            Metric::EachSecond { values, .. } => values.len(),
-            Metric::LatencyPercentiles { values, .. } => values.len(),
-        }
-    }
 }
 
 #[derive(PartialEq)]
@@ -275,11 +289,11 @@ fn error_message_insertion(messages: &mut Vec<String>, new_message: String) {
 
 impl ReportArchive {
     fn path(&self) -> PathBuf {
-        Self::last_run_path().join(self.tags.get_name())
+        Self::last_run_path().join(self.tags.get_file_name())
     }
 
     pub fn load(name: &str) -> Result<Self> {
-        match std::fs::read(Self::last_run_path().join(name)) {
+        match std::fs::read(Self::last_run_path().join(file_name_from_name(name))) {
             Ok(bytes) => bincode::serde::decode_from_slice(&bytes, BINCODE_CONFIG).map_err(|e|
                 anyhow!(e).context("The bench archive from the previous run is not a valid archive, maybe the format changed since the last run")
             ).map(|x| x.0),
@@ -289,7 +303,7 @@ impl ReportArchive {
     }
 
     pub fn load_baseline(name: &str) -> Result<Option<Self>> {
-        match std::fs::read(Self::baseline_path().join(name)) {
+        match std::fs::read(Self::baseline_path().join(file_name_from_name(name))) {
             Ok(bytes) => bincode::serde::decode_from_slice(&bytes, BINCODE_CONFIG)
                 .map_err(|e|
                     anyhow!(e).context("The bench archive from the baseline is not a valid archive, maybe the format changed since the baseline was set")
@@ -303,60 +317,87 @@ impl ReportArchive {
     pub fn reports_in_last_run() -> Vec<String> {
         let report_dir = Self::last_run_path();
         std::fs::create_dir_all(&report_dir).unwrap();
+        reports_in(&report_dir)
+    }
 
-        let mut reports: Vec<String> = std::fs::read_dir(report_dir)
-            .unwrap()
-            .map(|x| {
-                x.unwrap()
-                    .path()
-                    .file_name()
-                    .unwrap()
-                    .to_str()
-                    .unwrap()
-                    .to_owned()
-            })
-            .collect();
-        reports.sort();
-        reports
+    /// Returns an empty list if no baseline is set
+    pub(crate) fn reports_in_baseline() -> Vec<String> {
+        let report_dir = Self::baseline_path();
+        if report_dir.exists() {
+            reports_in(&report_dir)
+        } else {
+            vec![]
+        }
     }
 
     pub fn save(&self) {
         let path = self.path();
         std::fs::create_dir_all(path.parent().unwrap()).unwrap();
-        std::fs::write(
+        write_atomic(
             &path,
-            bincode::serde::encode_to_vec(self, BINCODE_CONFIG).unwrap(),
+            &bincode::serde::encode_to_vec(self, BINCODE_CONFIG).unwrap(),
         )
         .map_err(|e| panic!("Failed to write to {path:?} {e}"))
         .unwrap()
     }
 
+    /// Moves the archive of the named bench in the last run to the provided tags
+    pub(crate) fn retag(name: &str, tags: Tags) -> Result<()> {
+        let mut archive = Self::load(name)?;
+        let old_path = archive.path();
+        archive.tags = tags;
+        archive.save();
+        if archive.path() != old_path {
+            std::fs::remove_file(&old_path)
+                .with_context(|| format!("Failed to remove {old_path:?}"))?;
+        }
+        Ok(())
+    }
+
+    /// Adds a note to the archive of the named bench in the last run
+    pub(crate) fn add_info_message(name: &str, message: String) -> Result<()> {
+        let mut archive = Self::load(name)?;
+        archive.info_messages.push(message);
+        archive.save();
+        Ok(())
+    }
+
     pub(crate) fn clear_last_run() {
         let path = Self::last_run_path();
-        if path.exists() {
-            // Just an extra sanity check that we truly are deleting a last_run directory
-            assert_eq!(path.file_name().unwrap(), "last_run");
-            std::fs::remove_dir_all(path).unwrap();
-        }
+        // Just an extra sanity check that we truly are deleting a last_run directory
+        assert_eq!(path.file_name().unwrap(), "last_run");
+        remove_dir_atomic(&path);
     }
 
+    /// The caller should hold a [`crate::data::WriteLock`]
     pub fn set_baseline() {
-        Self::clear_baseline();
-
         let last_run_path = Self::last_run_path();
         let baseline_path = Self::baseline_path();
-        if last_run_path.exists() {
-            copy_dir::copy_dir(last_run_path, baseline_path).unwrap();
+
+        // Copy to a temporary directory first so that other processes never observe a partially copied baseline.
+        let tmp_baseline_path = tmp_path(&baseline_path, "tmp");
+        if tmp_baseline_path.exists() {
+            std::fs::remove_dir_all(&tmp_baseline_path).unwrap();
+        }
+        copy_dir::copy_dir(last_run_path, &tmp_baseline_path).unwrap();
+
+        // Move the old baseline aside rather than deleting it first, so that there is only a brief moment without a baseline
+        let old_baseline_path = tmp_path(&baseline_path, "remove");
+        if baseline_path.exists() {
+            std::fs::rename(&baseline_path, &old_baseline_path).unwrap();
+        }
+        std::fs::rename(&tmp_baseline_path, &baseline_path).unwrap();
+        if old_baseline_path.exists() {
+            std::fs::remove_dir_all(old_baseline_path).unwrap();
         }
     }
 
+    /// The caller should hold a [`crate::data::WriteLock`]
     pub fn clear_baseline() {
         let path = Self::baseline_path();
-        if path.exists() {
-            // Just an extra sanity check that we truly are deleting a baseline directory
-            assert_eq!(path.file_name().unwrap(), "baseline");
-            std::fs::remove_dir_all(path).unwrap();
-        }
+        // Just an extra sanity check that we truly are deleting a baseline directory
+        assert_eq!(path.file_name().unwrap(), "baseline");
+        remove_dir_atomic(&path);
     }
 
     pub fn last_run_path() -> PathBuf {
@@ -370,12 +411,49 @@ impl ReportArchive {
     }
 }
 
+fn reports_in(report_dir: &Path) -> Vec<String> {
+    let mut reports: Vec<String> = std::fs::read_dir(report_dir)
+        .unwrap()
+        .filter_map(|x| {
+            let path = x.unwrap().path();
+            let file_name = path.file_name().unwrap().to_str()?;
+            if is_hashed_file_name(file_name) {
+                // the name was truncated to fit in a file name, so the full name must be read from the archive
+                let bytes = std::fs::read(&path).ok()?;
+                let (archive, _): (ReportArchive, _) =
+                    bincode::serde::decode_from_slice(&bytes, BINCODE_CONFIG).ok()?;
+                Some(archive.tags.get_name())
+            } else {
+                // skip any files that were not created by windsock
+                Tags::name_from_file_name(file_name)
+            }
+        })
+        .collect();
+    reports.sort();
+    reports
+}
+
+/// Removes the directory by first renaming it so that other processes never observe a partially deleted directory.
+fn remove_dir_atomic(path: &Path) {
+    if path.exists() {
+        let tmp_path = tmp_path(path, "remove");
+        std::fs::rename(path, &tmp_path).unwrap();
+        std::fs::remove_dir_all(tmp_path).unwrap();
+    }
+}
+
 pub(crate) async fn report_builder(
     tags: Tags,
     mut rx: UnboundedReceiver<Report>,
-    requested_ops: Option<u64>,
+    load_profile: LoadProfile,
+    bencher_cpus: Option<CpuSet>,
     running_in_release: bool,
-) -> ReportArchive {
+) -> Result<ReportArchive> {
+    let requested_ops = if load_profile.is_varying() {
+        None
+    } else {
+        load_profile.peak()
+    };
     let mut external_report = None;
     let mut finished_in = None;
     let mut started = None;
@@ -383,6 +461,9 @@ pub(crate) async fn report_builder(
     let mut operations_report = None;
     let mut operation_times = vec![];
     let mut produce_times = vec![];
+    // The index into the times vecs at which the current second began
+    let mut operation_times_second_start = 0;
+    let mut produce_times_second_start = 0;
     let mut consume_times = vec![];
     let mut total_operation_time = Duration::from_secs(0);
     let mut total_produce_time = Duration::from_secs(0);
@@ -417,6 +498,11 @@ pub(crate) async fn report_builder(
                     error_message_insertion(&mut error_messages, message);
                     report.total_errors += 1;
                     total_operation_time += completed_in;
+                    let second = report.total_each_second.len().saturating_sub(1);
+                    if report.errors_each_second.len() <= second {
+                        report.errors_each_second.resize(second + 1, 0);
+                    }
+                    report.errors_each_second[second] += 1;
                 }
             }
             Report::ProduceCompletedIn(duration) => {
@@ -472,11 +558,23 @@ pub(crate) async fn report_builder(
                 );
                 if let Some(report) = operations_report.as_mut() {
                     report.total_each_second.push(0);
+                    report
+                        .time_percentiles_each_second
+                        .push(calculate_percentiles(
+                            operation_times[operation_times_second_start..].to_vec(),
+                        ));
+                    operation_times_second_start = operation_times.len();
                 }
                 if let Some(report) = pubsub_report.as_mut() {
                     report.produce_each_second.push(0);
                     report.consume_each_second.push(0);
                     report.backlog_each_second.push(report.total_backlog);
+                    report
+                        .produce_time_percentiles_each_second
+                        .push(calculate_percentiles(
+                            produce_times[produce_times_second_start..].to_vec(),
+                        ));
+                    produce_times_second_start = produce_times.len();
                 }
             }
             Report::FinishedIn(duration) => {
@@ -498,6 +596,13 @@ pub(crate) async fn report_builder(
         }
     }
 
+    if load_profile.is_varying() && !varying_profile_followed() {
+        return Err(anyhow!(
+            "The bench did not follow --load-profile \"{load_profile}\" so its results were discarded.\n\
+            Pass `BenchParameters::load_profile` to `BenchTask::spawn_tasks`, use `BenchTask::run_bench` or call `LoadProfile::rate_at`."
+        ));
+    }
+
     if let Some(external_report) = external_report {
         started = Some(external_report.bench_started_at);
         operations_report = external_report.operations_report;
@@ -518,6 +623,13 @@ pub(crate) async fn report_builder(
 
             // This is not a complete result so discard it.
             report.total_each_second.pop();
+            report
+                .errors_each_second
+                .resize(report.total_each_second.len(), 0);
+            if load_profile.is_varying() {
+                report.requested_each_second =
+                    load_profile.target_each_second(report.total_each_second.len());
+            }
         }
 
         if let Some(report) = pubsub_report.as_mut() {
@@ -536,12 +648,17 @@ pub(crate) async fn report_builder(
             // This is not a complete result so discard it.
             report.produce_each_second.pop();
             report.consume_each_second.pop();
+            if load_profile.is_varying() {
+                report.requested_produce_each_second =
+                    load_profile.target_each_second(report.produce_each_second.len());
+            }
         }
     }
 
     let archive = ReportArchive {
         bench_started_at: started.unwrap(),
         running_in_release,
+        bencher_cpus,
         tags,
         pubsub_report,
         error_messages,
@@ -550,7 +667,7 @@ pub(crate) async fn report_builder(
         metrics: vec![],
     };
     archive.save();
-    archive
+    Ok(archive)
 }
 
 fn mean_time(times: &[Duration], total_time: Duration) -> Option<Duration> {
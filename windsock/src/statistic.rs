@@ -0,0 +1,263 @@
+use crate::{
+    Goal, Metric,
+    capacity::CAPACITY_METRIC,
+    report::{Percentile, Percentiles, ReportArchive},
+    units::{Unit, format_duration},
+};
+use anyhow::{Error, Result, anyhow};
+use std::{fmt::Display, str::FromStr, time::Duration};
+use strum::IntoEnumIterator;
+
+/// A single value that can be extracted from a bench archive.
+/// Used wherever the user selects which result to display or compare, e.g. `--statistic p99`
+#[derive(Clone, PartialEq, Debug)]
+pub(crate) enum Statistic {
+    /// Operations per second
+    Ops,
+    /// Total operations
+    Total,
+    /// Total errored operations, produces and consumes
+    Errors,
+    /// The fraction of operations, produces and consumes that errored
+    ErrorRate,
+    Latency {
+        source: LatencySource,
+        kind: LatencyKind,
+    },
+    Produce,
+    Consume,
+    Backlog,
+    /// The highest operations per second sustained within the SLO, found by `--find-capacity`
+    Capacity,
+    /// A [`Metric::Total`] with the specified name
+    Metric(String),
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub(crate) enum LatencySource {
+    Operation,
+    Produce,
+    Consume,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub(crate) enum LatencyKind {
+    Mean,
+    Percentile(Percentile),
+}
+
+const HELP: &str = "Valid statistics are: ops, total, errors, error_rate, mean, min, max, p<percentile> (e.g. p50, p99, p99.9), produce, consume, backlog, capacity, metric:<name>.
+Latency statistics can be prefixed with produce_ or consume_ to select pubsub latencies, e.g. produce_p99";
+
+impl FromStr for Statistic {
+    type Err = Error;
+
+    fn from_str(text: &str) -> Result<Self> {
+        if let Some(name) = text.strip_prefix("metric:") {
+            return Ok(Statistic::Metric(name.to_owned()));
+        }
+
+        match text {
+            "ops" => return Ok(Statistic::Ops),
+            "total" => return Ok(Statistic::Total),
+            "errors" => return Ok(Statistic::Errors),
+            "error_rate" => return Ok(Statistic::ErrorRate),
+            "produce" => return Ok(Statistic::Produce),
+            "consume" => return Ok(Statistic::Consume),
+            "backlog" => return Ok(Statistic::Backlog),
+            "capacity" => return Ok(Statistic::Capacity),
+            _ => {}
+        }
+
+        let (source, latency) = if let Some(latency) = text.strip_prefix("produce_") {
+            (LatencySource::Produce, latency)
+        } else if let Some(latency) = text.strip_prefix("consume_") {
+            (LatencySource::Consume, latency)
+        } else {
+            (LatencySource::Operation, text)
+        };
+        let kind = if latency == "mean" {
+            LatencyKind::Mean
+        } else {
+            LatencyKind::Percentile(
+                parse_percentile(latency)
+                    .map_err(|_| anyhow!("Unknown statistic {text:?}\n{HELP}"))?,
+            )
+        };
+        Ok(Statistic::Latency { source, kind })
+    }
+}
+
+impl Display for Statistic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Statistic::Ops => write!(f, "ops"),
+            Statistic::Total => write!(f, "total"),
+            Statistic::Errors => write!(f, "errors"),
+            Statistic::ErrorRate => write!(f, "error_rate"),
+            Statistic::Produce => write!(f, "produce"),
+            Statistic::Consume => write!(f, "consume"),
+            Statistic::Backlog => write!(f, "backlog"),
+            Statistic::Capacity => write!(f, "capacity"),
+            Statistic::Metric(name) => write!(f, "metric:{name}"),
+            Statistic::Latency { source, kind } => {
+                match source {
+                    LatencySource::Operation => {}
+                    LatencySource::Produce => write!(f, "produce_")?,
+                    LatencySource::Consume => write!(f, "consume_")?,
+                }
+                match kind {
+                    LatencyKind::Mean => write!(f, "mean"),
+                    LatencyKind::Percentile(percentile) => {
+                        write!(f, "{}", percentile_name(*percentile))
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Parses a percentile from the name used to refer to it on the CLI, e.g. p99.9
+pub(crate) fn parse_percentile(text: &str) -> Result<Percentile> {
+    Percentile::iter()
+        .find(|x| percentile_name(*x) == text)
+        .ok_or_else(|| {
+            anyhow!(
+                "Unknown percentile {text:?}, valid percentiles are: {}",
+                Percentile::iter()
+                    .map(percentile_name)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )
+        })
+}
+
+/// The name used to refer to a percentile on the CLI, e.g. p99.9
+pub(crate) fn percentile_name(percentile: Percentile) -> String {
+    match percentile {
+        Percentile::Min => "min".to_owned(),
+        Percentile::Max => "max".to_owned(),
+        percentile => format!("p{}", percentile.name().trim()),
+    }
+}
+
+/// A value measured from an archive, along with how it is displayed and compared
+#[derive(Clone, Debug)]
+pub(crate) struct Measured {
+    /// The unformatted value in `unit`, used to compare against other measurements
+    pub(crate) value: f64,
+    pub(crate) unit: Unit,
+    pub(crate) display: String,
+    pub(crate) goal: Goal,
+}
+
+impl Measured {
+    pub(crate) fn new(value: f64, unit: Unit, goal: Goal) -> Self {
+        Measured {
+            value,
+            unit,
+            display: unit.format(value),
+            goal,
+        }
+    }
+
+    pub(crate) fn count(value: f64, goal: Goal) -> Self {
+        Measured::new(value, Unit::Count, goal)
+    }
+
+    /// Displayed from the duration itself, so that no precision is lost converting it to seconds and back
+    pub(crate) fn duration(duration: Duration, goal: Goal) -> Self {
+        Measured {
+            value: duration.as_secs_f64(),
+            unit: Unit::Seconds,
+            display: format_duration(duration),
+            goal,
+        }
+    }
+}
+
+impl Statistic {
+    /// Measures the statistic, returns None if the archive does not contain the statistic.
+    pub(crate) fn measure(&self, archive: &ReportArchive) -> Option<Measured> {
+        match self {
+            Statistic::Ops => archive.operations_report.as_ref().map(|report| {
+                Measured::count(
+                    report.total_operations_per_second as f64,
+                    Goal::BiggerIsBetter,
+                )
+            }),
+            Statistic::Total => archive
+                .operations_report
+                .as_ref()
+                .map(|report| Measured::count(report.total as f64, Goal::BiggerIsBetter)),
+            Statistic::Errors => error_counts(archive)
+                .map(|(errors, _)| Measured::count(errors as f64, Goal::SmallerIsBetter)),
+            Statistic::ErrorRate => error_counts(archive).map(|(errors, attempted)| {
+                let rate = if attempted == 0 {
+                    0.0
+                } else {
+                    errors as f64 / attempted as f64
+                };
+                Measured::new(rate, Unit::Ratio, Goal::SmallerIsBetter)
+            }),
+            Statistic::Produce => archive.pubsub_report.as_ref().map(|report| {
+                Measured::count(report.produce_per_second as f64, Goal::BiggerIsBetter)
+            }),
+            Statistic::Consume => archive.pubsub_report.as_ref().map(|report| {
+                Measured::count(report.consume_per_second as f64, Goal::BiggerIsBetter)
+            }),
+            Statistic::Backlog => archive
+                .pubsub_report
+                .as_ref()
+                .map(|report| Measured::count(report.total_backlog as f64, Goal::SmallerIsBetter)),
+            Statistic::Capacity => Statistic::Metric(CAPACITY_METRIC.to_owned()).measure(archive),
+            Statistic::Metric(name) => archive.metrics.iter().find_map(|metric| match metric {
+                Metric::Total {
+                    name: metric_name,
+                    value,
+                    unit,
+                    goal,
+                } if metric_name == name => Some(Measured::new(*value, *unit, *goal)),
+                _ => None,
+            }),
+            Statistic::Latency { source, kind } => {
+                let (mean, percentiles): (Option<Duration>, Percentiles) = match source {
+                    LatencySource::Operation => archive
+                        .operations_report
+                        .as_ref()
+                        .map(|x| (x.mean_time, x.time_percentiles))?,
+                    LatencySource::Produce => archive
+                        .pubsub_report
+                        .as_ref()
+                        .map(|x| (x.produce_mean_time, x.produce_time_percentiles))?,
+                    LatencySource::Consume => archive
+                        .pubsub_report
+                        .as_ref()
+                        .map(|x| (x.consume_mean_time, x.consume_time_percentiles))?,
+                };
+                let duration = match kind {
+                    LatencyKind::Mean => mean?,
+                    LatencyKind::Percentile(percentile) => percentiles[*percentile as usize],
+                };
+                Some(Measured::duration(duration, Goal::SmallerIsBetter))
+            }
+        }
+    }
+}
+
+/// The total errors and the total attempts of every operation, produce and consume in the bench.
+/// None if the bench reports neither operations nor pubsub results.
+fn error_counts(archive: &ReportArchive) -> Option<(u64, u64)> {
+    let operations = archive
+        .operations_report
+        .as_ref()
+        .map(|x| (x.total_errors, x.total + x.total_errors));
+    let pubsub = archive.pubsub_report.as_ref().map(|x| {
+        let errors = x.total_produce_error + x.total_consume_error;
+        (errors, x.total_produce + x.total_consume + errors)
+    });
+    match (operations, pubsub) {
+        (Some(a), Some(b)) => Some((a.0 + b.0, a.1 + b.1)),
+        (a, b) => a.or(b),
+    }
+}
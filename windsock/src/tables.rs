@@ -1,14 +1,22 @@
+mod delimited;
+mod markdown;
+mod terminal;
+
+pub(crate) use terminal::terminal_width;
+
 use crate::{
     Metric,
+    affinity::CpuSet,
     bench::Tags,
+    cli::{Section, SectionArgs, TableFormat},
     filter::Filter,
-    report::{MetricIdentifier, Percentile, ReportArchive},
+    report::{MetricIdentifier, ReportArchive},
+    statistic::{LatencyKind, LatencySource, Measured, Statistic},
+    units::Unit,
 };
-use anyhow::{Context, Result};
-use console::{Alignment, pad_str, pad_str_with, style};
+use anyhow::{Context, Result, anyhow};
 use serde::{Deserialize, Serialize};
-use std::{collections::HashSet, time::Duration};
-use strum::IntoEnumIterator;
+use std::collections::HashSet;
 
 pub(crate) struct ReportColumn {
     pub(crate) baseline: Option<ReportArchive>,
@@ -31,7 +39,7 @@ impl ReportColumn {
     }
 }
 
-pub fn compare_by_name(names: &str) -> Result<()> {
+pub fn compare_by_name(names: &str, format: TableFormat, sections: &SectionArgs) -> Result<()> {
     let columns: Result<Vec<ReportColumn>> =
         names.split_whitespace().map(ReportColumn::load).collect();
     let mut columns = columns?;
@@ -41,11 +49,11 @@ pub fn compare_by_name(names: &str) -> Result<()> {
         column.baseline = baseline.clone();
     }
 
-    display_compare_table(&columns);
+    display_compare_table(&columns, format, sections);
     Ok(())
 }
 
-pub fn compare_by_tags(arg: &str) -> Result<()> {
+pub fn compare_by_tags(arg: &str, format: TableFormat, sections: &SectionArgs) -> Result<()> {
     let mut split = arg.split_whitespace();
     let base_name = split.next().unwrap().to_owned();
     let base = ReportArchive::load(&base_name)?;
@@ -55,9 +63,9 @@ pub fn compare_by_tags(arg: &str) -> Result<()> {
 
     let filter = Filter::from_query(&tag_args)
         .with_context(|| format!("Failed to parse tag filter from {:?}", tag_args))?;
-    let archives: Result<Vec<ReportColumn>> = ReportArchive::reports_in_last_run()
+    let archives: Result<Vec<ReportColumn>> = reports_in_last_run_matching(&filter)?
         .iter()
-        .filter(|name| **name != base_name && filter.matches(&Tags::from_name(name)))
+        .filter(|name| **name != base_name)
         .map(|x| {
             Ok(ReportColumn {
                 baseline: Some(base.clone()),
@@ -75,17 +83,21 @@ pub fn compare_by_tags(arg: &str) -> Result<()> {
         },
     );
 
-    display_compare_table(&archives);
+    display_compare_table(&archives, format, sections);
 
     Ok(())
 }
 
-pub fn results(ignore_baseline: bool, filter: &str) -> Result<()> {
+pub fn results(
+    ignore_baseline: bool,
+    filter: &str,
+    format: TableFormat,
+    sections: &SectionArgs,
+) -> Result<()> {
     let filter = Filter::from_query(filter)
         .with_context(|| format!("Failed to parse tag filter from {:?}", filter))?;
-    let archives: Result<Vec<ReportColumn>> = ReportArchive::reports_in_last_run()
+    let archives: Result<Vec<ReportColumn>> = reports_in_last_run_matching(&filter)?
         .iter()
-        .filter(|name| filter.matches(&Tags::from_name(name)))
         .map(|x| {
             if ignore_baseline {
                 ReportColumn::load(x)
@@ -97,51 +109,318 @@ pub fn results(ignore_baseline: bool, filter: &str) -> Result<()> {
     let archives = archives?;
     if archives.iter().any(|x| x.baseline.is_some()) {
         // If there are any baselines then compare against baselines
-        display_baseline_compare_table(&archives);
+        display_baseline_compare_table(&archives, format, sections);
     } else {
         // Otherwise display just results without any comparison
-        display_results_table(&archives);
+        display_results_table(&archives, format, sections);
+    }
+
+    Ok(())
+}
+
+/// The statistics displayed by `summary` for benches with an operations report
+const SUMMARY_OPERATIONS_STATISTICS: [&str; 6] = ["ops", "mean", "p50", "p99", "max", "error_rate"];
+/// The statistics displayed by `summary` for benches with a pubsub report
+const SUMMARY_PUBSUB_STATISTICS: [&str; 4] = ["produce", "consume", "produce_p99", "backlog"];
+
+/// How the rows of the summary are sorted
+#[derive(Clone, Debug)]
+pub(crate) enum SortKey {
+    Name,
+    Statistic(Statistic),
+}
+
+impl std::str::FromStr for SortKey {
+    type Err = anyhow::Error;
+
+    fn from_str(text: &str) -> Result<Self> {
+        if text == "name" {
+            Ok(SortKey::Name)
+        } else {
+            text.parse().map(SortKey::Statistic)
+        }
+    }
+}
+
+/// Displays one row per bench containing its key statistics.
+pub fn summary(
+    ignore_baseline: bool,
+    filter: &str,
+    sort_by: &SortKey,
+    descending: bool,
+    format: TableFormat,
+) -> Result<()> {
+    let filter = Filter::from_query(filter)
+        .with_context(|| format!("Failed to parse tag filter from {:?}", filter))?;
+    let mut reports = reports_in_last_run_matching(&filter)?
+        .iter()
+        .map(|x| {
+            if ignore_baseline {
+                ReportColumn::load(x)
+            } else {
+                ReportColumn::load_with_baseline(x)
+            }
+        })
+        .collect::<Result<Vec<ReportColumn>>>()?;
+    if reports.is_empty() {
+        println!("Need at least one report to display a summary");
+        return Ok(());
+    }
+
+    match sort_by {
+        SortKey::Name => reports.sort_by_key(|x| x.current.tags.get_name()),
+        SortKey::Statistic(statistic) => reports.sort_by(|a, b| {
+            // benches without the statistic are always placed last
+            match (statistic.measure(&a.current), statistic.measure(&b.current)) {
+                (Some(a), Some(b)) if descending => b.value.total_cmp(&a.value),
+                (Some(a), Some(b)) => a.value.total_cmp(&b.value),
+                (Some(_), None) => std::cmp::Ordering::Less,
+                (None, Some(_)) => std::cmp::Ordering::Greater,
+                (None, None) => std::cmp::Ordering::Equal,
+            }
+        }),
+    }
+    if descending && matches!(sort_by, SortKey::Name) {
+        reports.reverse();
+    }
+
+    let mut intersection = reports[0].current.tags.clone();
+    for report in &reports {
+        intersection = intersection.intersection(&report.current.tags);
+    }
+
+    let mut statistics: Vec<Statistic> = vec![];
+    if reports
+        .iter()
+        .any(|x| x.current.operations_report.is_some())
+    {
+        statistics.extend(
+            SUMMARY_OPERATIONS_STATISTICS
+                .iter()
+                .map(|x| x.parse().unwrap()),
+        );
+    }
+    if reports.iter().any(|x| x.current.pubsub_report.is_some()) {
+        statistics.extend(SUMMARY_PUBSUB_STATISTICS.iter().map(|x| x.parse().unwrap()));
+    }
+
+    let mut rows = vec![
+        Row::Heading(format!("Summary for {}", intersection.get_name())),
+        Row::ColumnNames {
+            legend: "bench".to_owned(),
+            names: statistics.iter().map(|x| x.to_string()).collect(),
+        },
+    ];
+    for report in &reports {
+        // only the tags that differ between benches are needed to tell them apart
+        let unique_tags = Tags(
+            report
+                .current
+                .tags
+                .0
+                .iter()
+                .filter(|(key, _)| !intersection.0.contains_key(*key))
+                .map(|(key, value)| (key.clone(), value.clone()))
+                .collect(),
+        );
+        let legend = if unique_tags.0.is_empty() {
+            report.current.tags.get_name()
+        } else {
+            unique_tags.get_name()
+        };
+        rows.push(Row::Measurements {
+            legend,
+            measurements: statistics
+                .iter()
+                .map(|statistic| {
+                    Measurement::new(
+                        statistic.measure(&report.current),
+                        report.baseline.as_ref().and_then(|x| statistic.measure(x)),
+                    )
+                })
+                .collect(),
+        });
+    }
+
+    render(
+        &Table {
+            rows,
+            notes: notes(&reports),
+        },
+        format,
+    );
+    Ok(())
+}
+
+/// Displays a single statistic in a grid where the columns and rows are formed from the values of two tag keys.
+pub fn pivot(
+    ignore_baseline: bool,
+    filter: &str,
+    row_key: &str,
+    column_key: &str,
+    statistic: &Statistic,
+    format: TableFormat,
+) -> Result<()> {
+    let filter = Filter::from_query(filter)
+        .with_context(|| format!("Failed to parse tag filter from {:?}", filter))?;
+
+    let mut cells: Vec<(String, String, ReportColumn)> = vec![];
+    for name in reports_in_last_run_matching(&filter)? {
+        let tags = Tags::from_name(&name)?;
+        // benches without both tags have no place in the grid
+        let (Some(row), Some(column)) = (tags.0.get(row_key), tags.0.get(column_key)) else {
+            continue;
+        };
+        if let Some((_, _, existing)) = cells.iter().find(|(r, c, _)| r == row && c == column) {
+            return Err(anyhow!(
+                "The benches {:?} and {:?} both have {row_key}={row} and {column_key}={column}.\nUse the filter to select only one bench for each cell.",
+                existing.current.tags.get_name(),
+                name
+            ));
+        }
+        let column_report = if ignore_baseline {
+            ReportColumn::load(&name)?
+        } else {
+            ReportColumn::load_with_baseline(&name)?
+        };
+        cells.push((row.clone(), column.clone(), column_report));
+    }
+    if cells.is_empty() {
+        println!(
+            "No benches in the last run matched the filter and have both the tag keys {row_key:?} and {column_key:?}"
+        );
+        return Ok(());
     }
 
+    let rows = sorted_tag_values(cells.iter().map(|(row, _, _)| row));
+    let columns = sorted_tag_values(cells.iter().map(|(_, column, _)| column));
+
+    let mut intersection = cells[0].2.current.tags.clone();
+    for (_, _, report) in &cells {
+        intersection = intersection.intersection(&report.current.tags);
+    }
+
+    let mut table_rows = vec![
+        Row::Heading(format!("{statistic} for {}", intersection.get_name())),
+        Row::ColumnNames {
+            legend: format!("{row_key} \\ {column_key}"),
+            names: columns.clone(),
+        },
+    ];
+    for row in &rows {
+        table_rows.push(Row::Measurements {
+            legend: row.clone(),
+            measurements: columns
+                .iter()
+                .map(
+                    |column| match cells.iter().find(|(r, c, _)| r == row && c == column) {
+                        Some((_, _, report)) => Measurement::new(
+                            statistic.measure(&report.current),
+                            report.baseline.as_ref().and_then(|x| statistic.measure(x)),
+                        ),
+                        None => Measurement::new(None, None),
+                    },
+                )
+                .collect(),
+        });
+    }
+
+    let reports: Vec<ReportColumn> = cells.into_iter().map(|(_, _, report)| report).collect();
+    render(
+        &Table {
+            rows: table_rows,
+            notes: notes(&reports),
+        },
+        format,
+    );
+
     Ok(())
 }
 
-pub(crate) fn display_baseline_compare_table(reports: &[ReportColumn]) {
+/// Sorts numerically if all values are numbers, otherwise sorts alphabetically
+fn sorted_tag_values<'a>(values: impl Iterator<Item = &'a String>) -> Vec<String> {
+    let mut values: Vec<String> = values.cloned().collect();
+    values.sort();
+    values.dedup();
+    if values.iter().all(|x| x.parse::<f64>().is_ok()) {
+        values.sort_by(|a, b| {
+            a.parse::<f64>()
+                .unwrap()
+                .total_cmp(&b.parse::<f64>().unwrap())
+        });
+    }
+    values
+}
+
+pub(crate) fn reports_in_last_run_matching(filter: &Filter) -> Result<Vec<String>> {
+    let mut names = vec![];
+    for name in ReportArchive::reports_in_last_run() {
+        if filter.matches(&Tags::from_name(&name)?) {
+            names.push(name);
+        }
+    }
+    Ok(names)
+}
+
+pub(crate) fn display_baseline_compare_table(
+    reports: &[ReportColumn],
+    format: TableFormat,
+    sections: &SectionArgs,
+) {
     if reports.is_empty() {
         println!("Need at least one report to display baseline comparison");
         return;
     }
 
-    base(reports, "Comparison against baseline");
+    base(reports, "Comparison against baseline", format, sections);
 }
 
-pub(crate) fn display_compare_table(reports: &[ReportColumn]) {
+pub(crate) fn display_compare_table(
+    reports: &[ReportColumn],
+    format: TableFormat,
+    sections: &SectionArgs,
+) {
     if reports.len() < 2 {
         println!("Need at least two reports to display a comparison against first column");
         return;
     }
 
-    base(reports, "Comparison against first column");
+    base(reports, "Comparison against first column", format, sections);
 }
 
-pub(crate) fn display_results_table(reports: &[ReportColumn]) {
+pub(crate) fn display_results_table(
+    reports: &[ReportColumn],
+    format: TableFormat,
+    sections: &SectionArgs,
+) {
     if reports.is_empty() {
         println!("Need at least one report to display results");
         return;
     }
 
-    base(reports, "Results");
+    base(reports, "Results", format, sections);
 }
 
-fn base(reports: &[ReportColumn], table_type: &str) {
-    // if the user has set CARGO_TERM_COLOR to force cargo to use colors then they probably want us to use colors too
-    if std::env::var("CARGO_TERM_COLOR")
-        .map(|x| x.to_lowercase() == "always")
-        .unwrap_or(false)
-    {
-        console::set_colors_enabled(true);
+fn base(reports: &[ReportColumn], table_type: &str, format: TableFormat, sections: &SectionArgs) {
+    render(&build_table(reports, table_type, sections), format);
+}
+
+fn render(table: &Table, format: TableFormat) {
+    match format {
+        TableFormat::Terminal => terminal::render(table),
+        TableFormat::Markdown => markdown::render(table),
+        TableFormat::Csv => delimited::render(table, ','),
+        TableFormat::Tsv => delimited::render(table, '\t'),
     }
+}
 
+/// Builds the rows of a table comparing the reports, independent of how the table will be rendered.
+/// Every row that can be selected as a [`Statistic`] is measured by it, so that the table agrees with `check`, `pivot` and `summary`.
+pub(crate) fn build_table(
+    reports: &[ReportColumn],
+    table_type: &str,
+    sections: &SectionArgs,
+) -> Table {
     let mut intersection = reports[0].current.tags.clone();
     for report in reports {
         intersection = intersection.intersection(&report.current.tags);
@@ -189,351 +468,300 @@ fn base(reports: &[ReportColumn], table_type: &str) {
         .iter()
         .any(|x| x.current.operations_report.is_some())
     {
-        rows.push(Row::Heading("Opns (Operations)".to_owned()));
-        rows.push(Row::measurements(reports, "Total Opns", |report| {
-            report.operations_report.as_ref().map(|report| {
-                (
-                    report.total as f64,
-                    report.total.to_string(),
-                    Goal::BiggerIsBetter,
-                )
-            })
-        }));
-        rows.push(Row::measurements(reports, "Total Errors", |report| {
-            report.operations_report.as_ref().map(|report| {
-                (
-                    report.total_errors as f64,
-                    report.total_errors.to_string(),
-                    Goal::SmallerIsBetter,
-                )
-            })
-        }));
-        rows.push(Row::measurements(
-            reports,
-            "Target Opns Per Sec",
-            |report| {
+        if sections.shows(&[Section::Ops]) {
+            rows.push(Row::Heading("Opns (Operations)".to_owned()));
+            rows.push(Row::statistic(reports, "Total Opns", Statistic::Total));
+            rows.push(Row::measurements(reports, "Total Errors", |report| {
                 report.operations_report.as_ref().map(|report| {
-                    (
-                        report
-                            .requested_operations_per_second
-                            .map(|x| x as f64)
-                            .unwrap_or(f64::INFINITY),
-                        report
-                            .requested_operations_per_second
-                            .map(|x| x.to_string())
-                            .unwrap_or("MAX".to_owned()),
-                        Goal::BiggerIsBetter,
-                    )
+                    Measured::count(report.total_errors as f64, Goal::SmallerIsBetter)
                 })
-            },
-        ));
-        rows.push(Row::measurements(reports, "Opns Per Sec", |report| {
-            report.operations_report.as_ref().map(|report| {
-                (
-                    report.total_operations_per_second as f64,
-                    format!("{:.0}", report.total_operations_per_second),
-                    Goal::BiggerIsBetter,
-                )
-            })
-        }));
-        rows.push(Row::measurements(reports, "Errors Per Sec", |report| {
-            report.operations_report.as_ref().map(|report| {
-                (
-                    report.total_errors_per_second as f64,
-                    format!("{:.0}", report.total_errors_per_second),
-                    Goal::SmallerIsBetter,
-                )
-            })
-        }));
-
-        rows.push(Row::measurements(reports, "Opn Time Mean", |report| {
-            report.operations_report.as_ref().map(|report| {
-                (
-                    report
-                        .mean_time
-                        .map(|x| x.as_secs_f64())
-                        .unwrap_or_default(),
-                    duration_ms_opt(report.mean_time),
-                    Goal::SmallerIsBetter,
-                )
-            })
-        }));
-
-        rows.push(Row::Heading("Opn Time Percentiles".to_owned()));
-        for (i, p) in Percentile::iter().enumerate() {
-            rows.push(Row::measurements(reports, p.name(), |report| {
+            }));
+            rows.push(Row::measurements(
+                reports,
+                "Target Opns Per Sec",
+                |report| {
+                    report.operations_report.as_ref().map(|report| {
+                        target_rate(
+                            report.requested_operations_per_second,
+                            &report.requested_each_second,
+                        )
+                    })
+                },
+            ));
+            rows.push(Row::statistic(reports, "Opns Per Sec", Statistic::Ops));
+            rows.push(Row::measurements(reports, "Errors Per Sec", |report| {
                 report.operations_report.as_ref().map(|report| {
-                    (
-                        report.time_percentiles[i].as_secs_f64(),
-                        duration_ms(report.time_percentiles[i]),
-                        Goal::SmallerIsBetter,
-                    )
+                    Measured::count(report.total_errors_per_second as f64, Goal::SmallerIsBetter)
                 })
             }));
+
+            rows.push(Row::statistic(
+                reports,
+                "Opn Time Mean",
+                Statistic::Latency {
+                    source: LatencySource::Operation,
+                    kind: LatencyKind::Mean,
+                },
+            ));
+
+            rows.push(Row::Heading("Opn Time Percentiles".to_owned()));
+            for p in sections.percentiles() {
+                rows.push(Row::statistic(
+                    reports,
+                    p.name(),
+                    Statistic::Latency {
+                        source: LatencySource::Operation,
+                        kind: LatencyKind::Percentile(p),
+                    },
+                ));
+            }
         }
 
-        rows.push(Row::Heading("Opns Each Second".to_owned()));
-        for i in 0..reports
-            .iter()
-            .map(|x| {
+        if sections.shows(&[Section::Ops, Section::EachSecond]) {
+            if reports.iter().any(|x| {
                 x.current
                     .operations_report
                     .as_ref()
-                    .map(|report| report.total_each_second.len())
-                    .unwrap_or(0)
-            })
-            .max()
-            .unwrap()
-        {
-            rows.push(Row::measurements(reports, &i.to_string(), |report| {
-                report.operations_report.as_ref().and_then(|report| {
-                    report
-                        .total_each_second
-                        .get(i)
-                        .map(|value| (*value as f64, value.to_string(), Goal::BiggerIsBetter))
-                })
-            }));
+                    .is_some_and(|x| !x.requested_each_second.is_empty())
+            }) {
+                rows.extend(each_second_rows(
+                    reports,
+                    "Target Opns Each Second",
+                    sections,
+                    |report| {
+                        report.operations_report.as_ref().map(|report| {
+                            (
+                                report
+                                    .requested_each_second
+                                    .iter()
+                                    .map(|x| *x as f64)
+                                    .collect(),
+                                Unit::Count,
+                                Goal::BiggerIsBetter,
+                            )
+                        })
+                    },
+                ));
+            }
+            rows.extend(each_second_rows(
+                reports,
+                "Opns Each Second",
+                sections,
+                |report| {
+                    report.operations_report.as_ref().map(|report| {
+                        (
+                            report.total_each_second.iter().map(|x| *x as f64).collect(),
+                            Unit::Count,
+                            Goal::BiggerIsBetter,
+                        )
+                    })
+                },
+            ));
         }
     }
 
     if reports.iter().any(|x| x.current.pubsub_report.is_some()) {
-        rows.push(Row::Heading("Produce/Consume".to_owned()));
-        rows.push(Row::measurements(reports, "Total Produce", |report| {
-            report.pubsub_report.as_ref().map(|report| {
-                (
-                    report.total_produce as f64,
-                    report.total_produce.to_string(),
-                    Goal::BiggerIsBetter,
-                )
-            })
-        }));
-        rows.push(Row::measurements(
-            reports,
-            "Errors Total Produce",
-            |report| {
-                report.pubsub_report.as_ref().map(|report| {
-                    (
-                        report.total_produce_error as f64,
-                        report.total_produce_error.to_string(),
-                        Goal::SmallerIsBetter,
-                    )
-                })
-            },
-        ));
-        rows.push(Row::measurements(reports, "Total Consume", |report| {
-            report.pubsub_report.as_ref().map(|report| {
-                (
-                    report.total_consume as f64,
-                    report.total_consume.to_string(),
-                    Goal::BiggerIsBetter,
-                )
-            })
-        }));
-        rows.push(Row::measurements(
-            reports,
-            "Errors Total Consume",
-            |report| {
-                report.pubsub_report.as_ref().map(|report| {
-                    (
-                        report.total_consume_error as f64,
-                        report.total_consume_error.to_string(),
-                        Goal::SmallerIsBetter,
-                    )
-                })
-            },
-        ));
-        rows.push(Row::measurements(reports, "Total Backlog", |report| {
-            report.pubsub_report.as_ref().map(|report| {
-                (
-                    report.total_backlog as f64,
-                    report.total_backlog.to_string(),
-                    Goal::SmallerIsBetter,
-                )
-            })
-        }));
-
-        rows.push(Row::measurements(
-            reports,
-            "Target Produce Per Sec",
-            |report| {
-                report.pubsub_report.as_ref().map(|report| {
-                    (
-                        report
-                            .requested_produce_per_second
-                            .map(|x| x as f64)
-                            .unwrap_or(f64::INFINITY),
-                        report
-                            .requested_produce_per_second
-                            .map(|x| x.to_string())
-                            .unwrap_or("MAX".to_owned()),
-                        Goal::BiggerIsBetter,
-                    )
-                })
-            },
-        ));
-        rows.push(Row::measurements(reports, "Produce Per Sec", |report| {
-            report.pubsub_report.as_ref().map(|report| {
-                (
-                    report.produce_per_second as f64,
-                    format!("{:.0}", report.produce_per_second),
-                    Goal::BiggerIsBetter,
-                )
-            })
-        }));
-        rows.push(Row::measurements(
-            reports,
-            "Errors Produce Per Sec",
-            |report| {
-                report.pubsub_report.as_ref().map(|report| {
-                    (
-                        report.produce_errors_per_second as f64,
-                        format!("{:.0}", report.produce_errors_per_second),
-                        Goal::SmallerIsBetter,
-                    )
-                })
-            },
-        ));
-        rows.push(Row::measurements(reports, "Consume Per Sec", |report| {
-            report.pubsub_report.as_ref().map(|report| {
-                (
-                    report.consume_per_second as f64,
-                    format!("{:.0}", report.consume_per_second),
-                    Goal::BiggerIsBetter,
-                )
-            })
-        }));
-        rows.push(Row::measurements(
-            reports,
-            "Errors Consume Per Sec",
-            |report| {
-                report.pubsub_report.as_ref().map(|report| {
-                    (
-                        report.consume_errors_per_second as f64,
-                        format!("{:.0}", report.consume_errors_per_second),
-                        Goal::SmallerIsBetter,
-                    )
-                })
-            },
-        ));
-
-        rows.push(Row::measurements(reports, "Produce Time Mean", |report| {
-            report.pubsub_report.as_ref().map(|report| {
-                (
-                    report
-                        .produce_mean_time
-                        .map(|x| x.as_secs_f64())
-                        .unwrap_or_default(),
-                    duration_ms_opt(report.produce_mean_time),
-                    Goal::SmallerIsBetter,
-                )
-            })
-        }));
-        rows.push(Row::measurements(reports, "Consume Time Mean", |report| {
-            report.pubsub_report.as_ref().map(|report| {
-                (
-                    report
-                        .consume_mean_time
-                        .map(|x| x.as_secs_f64())
-                        .unwrap_or_default(),
-                    duration_ms_opt(report.consume_mean_time),
-                    Goal::SmallerIsBetter,
-                )
-            })
-        }));
-
-        rows.push(Row::Heading("Produce Time Percentiles".to_owned()));
-        for (i, p) in Percentile::iter().enumerate() {
-            rows.push(Row::measurements(reports, p.name(), |report| {
+        if sections.shows(&[Section::PubSub]) {
+            rows.push(Row::Heading("Produce/Consume".to_owned()));
+            rows.push(Row::measurements(reports, "Total Produce", |report| {
                 report.pubsub_report.as_ref().map(|report| {
-                    (
-                        report.produce_time_percentiles[i].as_secs_f64(),
-                        duration_ms(report.produce_time_percentiles[i]),
-                        Goal::SmallerIsBetter,
-                    )
+                    Measured::count(report.total_produce as f64, Goal::BiggerIsBetter)
                 })
             }));
-        }
-
-        rows.push(Row::Heading("Consume Time Percentiles".to_owned()));
-        for (i, p) in Percentile::iter().enumerate() {
-            rows.push(Row::measurements(reports, p.name(), |report| {
+            rows.push(Row::measurements(
+                reports,
+                "Errors Total Produce",
+                |report| {
+                    report.pubsub_report.as_ref().map(|report| {
+                        Measured::count(report.total_produce_error as f64, Goal::SmallerIsBetter)
+                    })
+                },
+            ));
+            rows.push(Row::measurements(reports, "Total Consume", |report| {
                 report.pubsub_report.as_ref().map(|report| {
-                    (
-                        report.consume_time_percentiles[i].as_secs_f64(),
-                        duration_ms(report.consume_time_percentiles[i]),
-                        Goal::SmallerIsBetter,
-                    )
+                    Measured::count(report.total_consume as f64, Goal::BiggerIsBetter)
                 })
             }));
-        }
+            rows.push(Row::measurements(
+                reports,
+                "Errors Total Consume",
+                |report| {
+                    report.pubsub_report.as_ref().map(|report| {
+                        Measured::count(report.total_consume_error as f64, Goal::SmallerIsBetter)
+                    })
+                },
+            ));
+            rows.push(Row::statistic(reports, "Total Backlog", Statistic::Backlog));
 
-        rows.push(Row::Heading("Produce Each Second".to_owned()));
-        for i in 0..reports
-            .iter()
-            .map(|x| {
-                x.current
-                    .pubsub_report
-                    .as_ref()
-                    .map(|report| report.produce_each_second.len())
-                    .unwrap_or(0)
-            })
-            .max()
-            .unwrap()
-        {
-            rows.push(Row::measurements(reports, &i.to_string(), |report| {
-                report.pubsub_report.as_ref().and_then(|report| {
-                    report
-                        .produce_each_second
-                        .get(i)
-                        .map(|value| (*value as f64, value.to_string(), Goal::BiggerIsBetter))
-                })
-            }));
-        }
+            rows.push(Row::measurements(
+                reports,
+                "Target Produce Per Sec",
+                |report| {
+                    report.pubsub_report.as_ref().map(|report| {
+                        target_rate(
+                            report.requested_produce_per_second,
+                            &report.requested_produce_each_second,
+                        )
+                    })
+                },
+            ));
+            rows.push(Row::statistic(
+                reports,
+                "Produce Per Sec",
+                Statistic::Produce,
+            ));
+            rows.push(Row::measurements(
+                reports,
+                "Errors Produce Per Sec",
+                |report| {
+                    report.pubsub_report.as_ref().map(|report| {
+                        Measured::count(
+                            report.produce_errors_per_second as f64,
+                            Goal::SmallerIsBetter,
+                        )
+                    })
+                },
+            ));
+            rows.push(Row::statistic(
+                reports,
+                "Consume Per Sec",
+                Statistic::Consume,
+            ));
+            rows.push(Row::measurements(
+                reports,
+                "Errors Consume Per Sec",
+                |report| {
+                    report.pubsub_report.as_ref().map(|report| {
+                        Measured::count(
+                            report.consume_errors_per_second as f64,
+                            Goal::SmallerIsBetter,
+                        )
+                    })
+                },
+            ));
 
-        rows.push(Row::Heading("Consume Each Second".to_owned()));
-        for i in 0..reports
-            .iter()
-            .map(|x| {
-                x.current
-                    .pubsub_report
-                    .as_ref()
-                    .map(|report| report.consume_each_second.len())
-                    .unwrap_or(0)
-            })
-            .max()
-            .unwrap()
-        {
-            rows.push(Row::measurements(reports, &i.to_string(), |report| {
-                report.pubsub_report.as_ref().and_then(|report| {
-                    report
-                        .consume_each_second
-                        .get(i)
-                        .map(|value| (*value as f64, value.to_string(), Goal::BiggerIsBetter))
-                })
-            }));
+            rows.push(Row::statistic(
+                reports,
+                "Produce Time Mean",
+                Statistic::Latency {
+                    source: LatencySource::Produce,
+                    kind: LatencyKind::Mean,
+                },
+            ));
+            rows.push(Row::statistic(
+                reports,
+                "Consume Time Mean",
+                Statistic::Latency {
+                    source: LatencySource::Consume,
+                    kind: LatencyKind::Mean,
+                },
+            ));
+
+            rows.push(Row::Heading("Produce Time Percentiles".to_owned()));
+            for p in sections.percentiles() {
+                rows.push(Row::statistic(
+                    reports,
+                    p.name(),
+                    Statistic::Latency {
+                        source: LatencySource::Produce,
+                        kind: LatencyKind::Percentile(p),
+                    },
+                ));
+            }
+
+            rows.push(Row::Heading("Consume Time Percentiles".to_owned()));
+            for p in sections.percentiles() {
+                rows.push(Row::statistic(
+                    reports,
+                    p.name(),
+                    Statistic::Latency {
+                        source: LatencySource::Consume,
+                        kind: LatencyKind::Percentile(p),
+                    },
+                ));
+            }
         }
 
-        rows.push(Row::Heading("Total Backlog Each Second".to_owned()));
-        for i in 0..reports
-            .iter()
-            .map(|x| {
+        if sections.shows(&[Section::PubSub, Section::EachSecond]) {
+            if reports.iter().any(|x| {
                 x.current
                     .pubsub_report
                     .as_ref()
-                    .map(|report| report.backlog_each_second.len())
-                    .unwrap_or(0)
-            })
-            .max()
-            .unwrap()
-        {
-            rows.push(Row::measurements(reports, &i.to_string(), |report| {
-                report.pubsub_report.as_ref().and_then(|report| {
-                    report
-                        .backlog_each_second
-                        .get(i)
-                        .map(|value| (*value as f64, value.to_string(), Goal::SmallerIsBetter))
-                })
-            }));
+                    .is_some_and(|x| !x.requested_produce_each_second.is_empty())
+            }) {
+                rows.extend(each_second_rows(
+                    reports,
+                    "Target Produce Each Second",
+                    sections,
+                    |report| {
+                        report.pubsub_report.as_ref().map(|report| {
+                            (
+                                report
+                                    .requested_produce_each_second
+                                    .iter()
+                                    .map(|x| *x as f64)
+                                    .collect(),
+                                Unit::Count,
+                                Goal::BiggerIsBetter,
+                            )
+                        })
+                    },
+                ));
+            }
+            rows.extend(each_second_rows(
+                reports,
+                "Produce Each Second",
+                sections,
+                |report| {
+                    report.pubsub_report.as_ref().map(|report| {
+                        (
+                            report
+                                .produce_each_second
+                                .iter()
+                                .map(|x| *x as f64)
+                                .collect(),
+                            Unit::Count,
+                            Goal::BiggerIsBetter,
+                        )
+                    })
+                },
+            ));
+            rows.extend(each_second_rows(
+                reports,
+                "Consume Each Second",
+                sections,
+                |report| {
+                    report.pubsub_report.as_ref().map(|report| {
+                        (
+                            report
+                                .consume_each_second
+                                .iter()
+                                .map(|x| *x as f64)
+                                .collect(),
+                            Unit::Count,
+                            Goal::BiggerIsBetter,
+                        )
+                    })
+                },
+            ));
+            rows.extend(each_second_rows(
+                reports,
+                "Total Backlog Each Second",
+                sections,
+                |report| {
+                    report.pubsub_report.as_ref().map(|report| {
+                        (
+                            report
+                                .backlog_each_second
+                                .iter()
+                                .map(|x| *x as f64)
+                                .collect(),
+                            Unit::Count,
+                            Goal::SmallerIsBetter,
+                        )
+                    })
+                },
+            ));
         }
     }
 
@@ -545,53 +773,56 @@ fn base(reports: &[ReportColumn], table_type: &str) {
             }
         }
     }
+    let shows_metric =
+        |name: &str| sections.shows(&[Section::Metrics, Section::Metric(name.to_owned())]);
+
+    let totals: Vec<&MetricIdentifier> = metrics_to_display
+        .iter()
+        .filter(|x| matches!(x, MetricIdentifier::Total { name } if shows_metric(name)))
+        .collect();
+    if !totals.is_empty() {
+        rows.push(Row::Heading("Metrics".to_owned()));
+    }
+    for metric_identifier in totals {
+        let MetricIdentifier::Total { name } = metric_identifier else {
+            unreachable!()
+        };
+        rows.push(Row::statistic(
+            reports,
+            name,
+            Statistic::Metric(name.clone()),
+        ));
+    }
+
     for metric_identifier in metrics_to_display {
         match &metric_identifier {
-            MetricIdentifier::Total { name } => {
-                rows.push(Row::measurements(reports, name, |report| {
-                    report
-                        .metrics
-                        .iter()
-                        .find(|metric| metric.identifier() == metric_identifier)
-                        .map(|metric| match metric {
-                            Metric::Total {
-                                compare,
-                                value,
-                                goal,
-                                ..
-                            } => (*compare, value.to_owned(), *goal),
-                            _ => unreachable!(),
-                        })
-                }));
-            }
+            MetricIdentifier::Total { .. } => {}
             MetricIdentifier::EachSecond { name } => {
-                rows.push(Row::Heading(format!("{name} Each Second")));
-                for i in 0..reports
-                    .iter()
-                    .map(|x| {
-                        x.current
-                            .metrics
-                            .iter()
-                            .find(|x| x.identifier() == metric_identifier)
-                            .map(|metric| metric.len())
-                            .unwrap_or(0)
-                    })
-                    .max()
-                    .unwrap()
-                {
-                    rows.push(Row::measurements(reports, &i.to_string(), |report| {
-                        report
-                            .metrics
-                            .iter()
-                            .find(|x| x.identifier() == metric_identifier)
-                            .and_then(|metric| match metric {
-                                Metric::EachSecond { values, .. } => values.get(i).cloned(),
-                                _ => unreachable!(),
-                            })
-                    }));
+                if sections.shows(&[
+                    Section::Metrics,
+                    Section::Metric(name.clone()),
+                    Section::EachSecond,
+                ]) {
+                    rows.extend(each_second_rows(
+                        reports,
+                        &format!("{name} Each Second"),
+                        sections,
+                        |report| {
+                            report
+                                .metrics
+                                .iter()
+                                .find(|x| x.identifier() == metric_identifier)
+                                .map(|metric| match metric {
+                                    Metric::EachSecond {
+                                        values, unit, goal, ..
+                                    } => (values.clone(), *unit, *goal),
+                                    _ => unreachable!(),
+                                })
+                        },
+                    ));
                 }
             }
-            MetricIdentifier::LatencyPercentiles { name } => {
+            MetricIdentifier::LatencyPercentiles { name } if shows_metric(name) => {
                 rows.push(Row::Heading(format!("{name} Percentiles")));
                 for (i, largest_col) in reports
                     .iter()
@@ -629,242 +860,226 @@ fn base(reports: &[ReportColumn], table_type: &str) {
                     ));
                 }
             }
+            MetricIdentifier::LatencyPercentiles { .. } => {}
         }
     }
 
-    // the width of the legend column
-    let legend_width: usize = rows
+    Table {
+        rows,
+        notes: notes(reports),
+    }
+}
+
+/// The requested rate of a bench, the mean of the targets of each second when the rate varied according to a load profile
+fn target_rate(requested: Option<u64>, requested_each_second: &[u64]) -> Measured {
+    match requested {
+        Some(rate) => Measured::count(rate as f64, Goal::BiggerIsBetter),
+        None if !requested_each_second.is_empty() => {
+            let mean = (requested_each_second.iter().sum::<u64>() as f64
+                / requested_each_second.len() as f64)
+                .round();
+            let mut measured = Measured::count(mean, Goal::BiggerIsBetter);
+            measured.display = format!("{} mean", measured.display);
+            measured
+        }
+        None => Measured {
+            value: f64::INFINITY,
+            unit: Unit::Count,
+            display: "MAX".to_owned(),
+            goal: Goal::BiggerIsBetter,
+        },
+    }
+}
+
+/// The rows of a series of values recorded each second, preceded by a heading.
+/// When a window is specified each row instead summarises a window of seconds.
+fn each_second_rows<F: Fn(&ReportArchive) -> Option<(Vec<f64>, Unit, Goal)>>(
+    reports: &[ReportColumn],
+    heading: &str,
+    sections: &SectionArgs,
+    f: F,
+) -> Vec<Row> {
+    let window = sections.each_second_window.map(|x| x.get()).unwrap_or(1);
+    let statistic = sections.each_second_statistic.unwrap_or_default();
+    let length = reports
         .iter()
-        .skip(1) // skip the main heading because its big and its alignment doesnt matter
-        .map(|x| match x {
-            Row::Heading(heading) => heading.len(),
-            Row::ColumnNames { legend, .. } => legend.len(),
-            Row::Measurements { legend, .. } => legend.len(),
-        })
+        .map(|x| f(&x.current).map(|x| x.0.len()).unwrap_or(0))
         .max()
-        .unwrap_or(10);
-    // the width of the comparison component of each column
-    let comparison_widths: Vec<usize> = reports
-        .iter()
-        .enumerate()
-        .map(|(i, _)| {
-            rows.iter()
-                .map(|x| match x {
-                    Row::Heading(_) => 0,
-                    Row::ColumnNames { .. } => 0,
-                    Row::Measurements { measurements, .. } => measurements[i].comparison.len() + 1, // + 1 ensures we get separation from the previous column
-                })
-                .max()
-                .unwrap()
-        })
-        .collect();
-    // the width of each entire column
-    let column_widths: Vec<usize> = reports
-        .iter()
-        .enumerate()
-        .map(|(i, _)| {
-            rows.iter()
-                .map(|x| match x {
-                    Row::Heading(_) => 0,                                 // Ignore these
-                    Row::ColumnNames { names, .. } => names[i].len() + 1, // + 1 ensures we get separation from the previous column
-                    Row::Measurements { measurements, .. } => {
-                        measurements[i].value.len() + 1 // ensures we get seperation from the previous column
-                        + comparison_widths[i]
-                    }
-                })
-                .max()
-                .unwrap()
-        })
-        .collect();
-    let total_width = legend_width + column_widths.iter().sum::<usize>();
-
-    for row in rows {
-        match row {
-            Row::Heading(heading) => {
-                println!(
-                    "{}",
-                    style(pad_str_with(
-                        &format!("{} ", heading),
-                        total_width,
-                        Alignment::Left,
-                        None,
-                        '═'
-                    ))
-                    .yellow()
-                    .bold()
-                )
-            }
-            Row::ColumnNames { legend, names } => {
-                print!(
-                    "{}",
-                    style(pad_str(&legend, legend_width, Alignment::Right, None))
-                        .yellow()
-                        .bold()
-                );
-                for (i, name) in names.into_iter().enumerate() {
-                    print!(
-                        " {}",
-                        style(pad_str_with(
-                            &name,
-                            column_widths[i] - 1,
-                            Alignment::Center,
-                            None,
-                            '─',
-                        ))
-                    )
-                }
-                println!()
-            }
-            Row::Measurements {
-                legend,
-                measurements,
-            } => {
-                print!(
-                    "{}",
-                    style(pad_str(&legend, legend_width, Alignment::Right, None))
-                        .yellow()
-                        .bold()
-                );
-                for (i, measurement) in measurements.into_iter().enumerate() {
-                    let colorer = match measurement.color {
-                        Color::Good => |x| style(x).green(),
-                        Color::Bad => |x| style(x).red(),
-                        Color::Neutral => |x| style(x).dim(),
-                    };
-                    let contents = format!(
-                        "{}{}",
-                        measurement.value,
-                        colorer(pad_str(
-                            &measurement.comparison,
-                            comparison_widths[i],
-                            Alignment::Right,
-                            None
-                        )),
-                    );
-                    print!(
-                        "{}",
-                        pad_str(&contents, column_widths[i], Alignment::Right, None),
-                    );
-                }
-                println!()
-            }
-        }
+        .unwrap();
+
+    let mut rows = vec![Row::Heading(if window == 1 {
+        heading.to_owned()
+    } else {
+        format!("{heading} ({statistic} of {window}s windows)")
+    })];
+    for start in (0..length).step_by(window) {
+        let end = (start + window).min(length);
+        let legend = if window == 1 {
+            start.to_string()
+        } else {
+            format!("{start}-{}", end - 1)
+        };
+        rows.push(Row::measurements(reports, &legend, |report| {
+            let (values, unit, goal) = f(report)?;
+            let value = statistic.apply(values.get(start..end.min(values.len()))?)?;
+            Some(Measured::new(value, unit, goal))
+        }));
     }
+    rows
+}
 
+/// The errors, warnings and info messages of the reports
+fn notes(reports: &[ReportColumn]) -> Vec<Note> {
+    let mut notes = vec![];
     for report in reports {
         if !report.current.error_messages.is_empty() {
-            let error = format!(
-                "Bench encountered errors: {}",
-                report.current.tags.get_name()
-            );
-            println!("{}", style(error).red().bold());
-            for (i, message) in report.current.error_messages.iter().enumerate() {
-                println!("    {i}.  {message}");
-            }
+            notes.push(Note {
+                kind: NoteKind::Error,
+                heading: format!(
+                    "Bench encountered errors: {}",
+                    report.current.tags.get_name()
+                ),
+                messages: report.current.error_messages.clone(),
+            });
         }
 
         if let Some(baseline) = &report.baseline {
             if !baseline.error_messages.is_empty() {
-                let error = format!(
-                    "Bench baseline encountered errors: {}",
-                    report.current.tags.get_name()
-                );
-                println!("{}", style(error).red().bold());
-                for (i, message) in report.current.error_messages.iter().enumerate() {
-                    println!("    {i}.  {message}");
-                }
+                notes.push(Note {
+                    kind: NoteKind::Error,
+                    heading: format!(
+                        "Bench baseline encountered errors: {}",
+                        report.current.tags.get_name()
+                    ),
+                    messages: baseline.error_messages.clone(),
+                });
             }
         }
     }
 
-    let errors_found = reports.iter().any(|x| {
-        !x.current.error_messages.is_empty()
-            || x.baseline
-                .as_ref()
-                .map(|x| !x.error_messages.is_empty())
-                .unwrap_or(false)
-    });
-    let not_running_in_release_found = reports.iter().any(|x| {
-        !x.current.running_in_release
-            || x.baseline
-                .as_ref()
-                .map(|x| !x.running_in_release)
-                .unwrap_or(false)
-    });
-    let info_found = reports.iter().any(|x| {
-        !x.current.info_messages.is_empty()
-            || x.baseline
-                .as_ref()
-                .map(|x| !x.info_messages.is_empty())
-                .unwrap_or(false)
-    });
-
-    if errors_found && not_running_in_release_found {
-        // ensure these two sections are kept apart
-        println!();
-    }
-
     for report in reports {
         if !report.current.running_in_release {
-            let error = format!(
-                "Bench results invalid! Bench compiled with non-release profile: {}",
-                report.current.tags.get_name()
-            );
-            println!("{}", style(error).red().bold());
+            notes.push(Note {
+                kind: NoteKind::Invalid,
+                heading: format!(
+                    "Bench results invalid! Bench compiled with non-release profile: {}",
+                    report.current.tags.get_name()
+                ),
+                messages: vec![],
+            });
         }
 
         if let Some(baseline) = &report.baseline {
             if !baseline.running_in_release {
-                let error = format!(
-                    "Baseline bench results invalid! Baseline bench compiled with non-release profile: {}",
-                    baseline.tags.get_name()
-                );
-                println!("{}", style(error).red().bold());
+                notes.push(Note {
+                    kind: NoteKind::Invalid,
+                    heading: format!(
+                        "Baseline bench results invalid! Baseline bench compiled with non-release profile: {}",
+                        baseline.tags.get_name()
+                    ),
+                    messages: vec![],
+                });
             }
         }
     }
 
-    #[allow(clippy::nonminimal_bool)]
-    if info_found
-        && (not_running_in_release_found || (errors_found && !not_running_in_release_found))
-    {
-        // ensure these two sections are kept apart
-        println!();
+    for report in reports {
+        if let Some(baseline) = &report.baseline {
+            if baseline.bencher_cpus != report.current.bencher_cpus {
+                notes.push(Note {
+                    kind: NoteKind::Info,
+                    heading: format!(
+                        "Bench and baseline were pinned to different CPUs, results may not be comparable: {}",
+                        report.current.tags.get_name()
+                    ),
+                    messages: vec![
+                        format!("bench: {}", describe_cpus(&report.current.bencher_cpus)),
+                        format!("baseline: {}", describe_cpus(&baseline.bencher_cpus)),
+                    ],
+                });
+            }
+        }
     }
 
     for report in reports {
         if !report.current.info_messages.is_empty() {
-            let error = format!("notes for {}", report.current.tags.get_name());
-            println!("{}", style(error).blue().bold());
-            for (i, message) in report.current.info_messages.iter().enumerate() {
-                println!("    {i}.  {message}");
-            }
+            notes.push(Note {
+                kind: NoteKind::Info,
+                heading: format!("notes for {}", report.current.tags.get_name()),
+                messages: report.current.info_messages.clone(),
+            });
         }
 
         if let Some(baseline) = &report.baseline {
             if !baseline.info_messages.is_empty() {
-                let error = format!("notes for baseline {}", report.current.tags.get_name());
-                println!("{}", style(error).blue().bold());
-                for (i, message) in report.current.info_messages.iter().enumerate() {
-                    println!("    {i}.  {message}");
-                }
+                notes.push(Note {
+                    kind: NoteKind::Info,
+                    heading: format!("notes for baseline {}", report.current.tags.get_name()),
+                    messages: baseline.info_messages.clone(),
+                });
             }
         }
     }
+
+    notes
 }
 
-fn duration_ms(duration: Duration) -> String {
-    format!("{:.3}ms", duration.as_micros() as f32 / 1000.0)
+fn describe_cpus(cpus: &Option<CpuSet>) -> String {
+    match cpus {
+        Some(cpus) => format!("pinned to CPUs {cpus}"),
+        None => "not pinned".to_owned(),
+    }
 }
 
-fn duration_ms_opt(duration: Option<Duration>) -> String {
-    match duration {
-        Some(duration) => format!("{:.3}ms", duration.as_micros() as f32 / 1000.0),
-        None => "N/A".to_owned(),
+/// A table of results that can be rendered in various formats.
+pub(crate) struct Table {
+    pub(crate) rows: Vec<Row>,
+    /// Messages to display after the table
+    pub(crate) notes: Vec<Note>,
+}
+
+impl Table {
+    pub(crate) fn column_count(&self) -> usize {
+        self.rows
+            .iter()
+            .find_map(|row| match row {
+                Row::Heading(_) => None,
+                Row::ColumnNames { names, .. } => Some(names.len()),
+                Row::Measurements { measurements, .. } => Some(measurements.len()),
+            })
+            .unwrap_or(0)
+    }
+
+    /// A single line label for each column, formed from the `Row::ColumnNames` rows.
+    /// Used by renderers that need a header row.
+    pub(crate) fn column_labels(&self) -> Vec<String> {
+        let column_names: Vec<(&String, &Vec<String>)> = self
+            .rows
+            .iter()
+            .filter_map(|row| match row {
+                Row::ColumnNames { legend, names } => Some((legend, names)),
+                _ => None,
+            })
+            .collect();
+        let column_count = self.column_count();
+        (0..column_count)
+            .map(|i| match column_names.as_slice() {
+                [] if column_count == 1 => "value".to_owned(),
+                [] => format!("column {i}"),
+                [(_, names)] => names[i].clone(),
+                column_names => column_names
+                    .iter()
+                    .filter(|(_, names)| !names[i].is_empty())
+                    .map(|(legend, names)| format!("{}={}", legend.trim(), names[i]))
+                    .collect::<Vec<_>>()
+                    .join(" "),
+            })
+            .collect()
     }
 }
 
-enum Row {
+#[derive(Clone)]
+pub(crate) enum Row {
     Heading(String),
     ColumnNames {
         legend: String,
@@ -876,10 +1091,75 @@ enum Row {
     },
 }
 
-struct Measurement {
-    value: String,
-    comparison: String,
-    color: Color,
+#[derive(Clone)]
+pub(crate) struct Measurement {
+    /// The value formatted for display
+    pub(crate) value: String,
+    /// The unformatted value and its unit, None if there is no value.
+    /// Not finite if the value has no numeric representation, e.g. an unlimited rate.
+    pub(crate) raw: Option<(f64, Unit)>,
+    pub(crate) comparison: String,
+    pub(crate) color: Color,
+}
+
+impl Measurement {
+    /// Creates a measurement of `current` that is compared against `baseline`
+    pub(crate) fn new(current: Option<Measured>, baseline: Option<Measured>) -> Measurement {
+        let raw = current.as_ref().map(|x| (x.value, x.unit));
+        let (value, comparison, comparison_raw, goal) = if let Some(current) = current {
+            if let Some(base) = baseline {
+                let comparison_raw: f64 = (current.value - base.value) / base.value * 100.0;
+                let comparison = if comparison_raw.is_nan() {
+                    "-".into()
+                } else {
+                    format!("{:+.1}%", comparison_raw)
+                };
+
+                (current.display, comparison, comparison_raw, current.goal)
+            } else {
+                (current.display, "".to_owned(), 0.0, Goal::BiggerIsBetter)
+            }
+        } else {
+            ("".to_owned(), "".to_owned(), 0.0, Goal::BiggerIsBetter)
+        };
+
+        let color = if comparison_raw > 5.0 {
+            if let Goal::BiggerIsBetter = goal {
+                Color::Good
+            } else {
+                Color::Bad
+            }
+        } else if comparison_raw < -5.0 {
+            if let Goal::SmallerIsBetter = goal {
+                Color::Good
+            } else {
+                Color::Bad
+            }
+        } else {
+            Color::Neutral
+        };
+        Measurement {
+            value,
+            raw,
+            comparison,
+            color,
+        }
+    }
+}
+
+pub(crate) struct Note {
+    pub(crate) kind: NoteKind,
+    pub(crate) heading: String,
+    pub(crate) messages: Vec<String>,
+}
+
+/// Notes are ordered by their kind and renderers keep each kind visually separate.
+#[derive(PartialEq)]
+pub(crate) enum NoteKind {
+    Error,
+    /// The results should not be trusted, e.g. because they were not compiled in release
+    Invalid,
+    Info,
 }
 
 /// Defines wether a change in results is good or bad.
@@ -892,14 +1172,20 @@ pub enum Goal {
     None,
 }
 
-enum Color {
+#[derive(Clone, Copy)]
+pub(crate) enum Color {
     Good,
     Bad,
     Neutral,
 }
 
 impl Row {
-    fn measurements<F: Fn(&ReportArchive) -> Option<(f64, String, Goal)>>(
+    /// A row of the statistic measured in each report
+    fn statistic(reports: &[ReportColumn], legend: &str, statistic: Statistic) -> Row {
+        Row::measurements(reports, legend, |report| statistic.measure(report))
+    }
+
+    fn measurements<F: Fn(&ReportArchive) -> Option<Measured>>(
         reports: &[ReportColumn],
         legend: &str,
         f: F,
@@ -907,46 +1193,7 @@ impl Row {
         let legend = legend.to_owned();
         let measurements = reports
             .iter()
-            .map(|x| {
-                let (value, comparison, comparison_raw, goal) =
-                    if let Some((compare, value, goal)) = f(&x.current) {
-                        if let Some((base, _, _)) = x.baseline.as_ref().and_then(&f) {
-                            let comparison_raw: f64 = (compare - base) / base * 100.0;
-                            let comparison = if comparison_raw.is_nan() {
-                                "-".into()
-                            } else {
-                                format!("{:+.1}%", comparison_raw)
-                            };
-
-                            (value, comparison, comparison_raw, goal)
-                        } else {
-                            (value, "".to_owned(), 0.0, Goal::BiggerIsBetter)
-                        }
-                    } else {
-                        ("".to_owned(), "".to_owned(), 0.0, Goal::BiggerIsBetter)
-                    };
-
-                let color = if comparison_raw > 5.0 {
-                    if let Goal::BiggerIsBetter = goal {
-                        Color::Good
-                    } else {
-                        Color::Bad
-                    }
-                } else if comparison_raw < -5.0 {
-                    if let Goal::SmallerIsBetter = goal {
-                        Color::Good
-                    } else {
-                        Color::Bad
-                    }
-                } else {
-                    Color::Neutral
-                };
-                Measurement {
-                    value,
-                    comparison,
-                    color,
-                }
-            })
+            .map(|x| Measurement::new(f(&x.current), x.baseline.as_ref().and_then(&f)))
             .collect();
         Row::Measurements {
             legend,
@@ -0,0 +1,148 @@
+use super::{Row, Table};
+
+/// Renders the table as delimiter separated values, e.g. CSV or TSV, suitable for importing into a spreadsheet.
+///
+/// Every measurement is split into a value column and a comparison column.
+/// Values are written unformatted so that spreadsheets can use them as numbers, e.g. latencies in seconds,
+/// the unit of each row is written in the unit column.
+/// Headings are not rows in their own right but are instead included in the section column of each row.
+/// Notes are written to stderr so that stdout contains only the table.
+pub(crate) fn render(table: &Table, delimiter: char) {
+    print!("{}", records(table, delimiter));
+
+    for note in &table.notes {
+        eprintln!("{}", note.heading);
+        for (i, message) in note.messages.iter().enumerate() {
+            eprintln!("    {i}.  {message}");
+        }
+    }
+}
+
+/// The header and one record for each row of measurements, each terminated by a newline
+fn records(table: &Table, delimiter: char) -> String {
+    let mut output = String::new();
+    let mut record = |fields: Vec<&str>| {
+        let fields: Vec<String> = fields.into_iter().map(|x| escape(x, delimiter)).collect();
+        output.push_str(&fields.join(&delimiter.to_string()));
+        output.push('\n');
+    };
+
+    let column_labels = table.column_labels();
+    let mut header = vec!["section", "measurement", "unit"];
+    let comparison_labels: Vec<String> = column_labels
+        .iter()
+        .map(|x| format!("{x} comparison"))
+        .collect();
+    for (label, comparison_label) in column_labels.iter().zip(&comparison_labels) {
+        header.push(label);
+        header.push(comparison_label);
+    }
+    record(header);
+
+    let mut section = "";
+    for row in &table.rows {
+        match row {
+            Row::Heading(heading) => section = heading,
+            // The column names are included in the header instead
+            Row::ColumnNames { .. } => {}
+            Row::Measurements {
+                legend,
+                measurements,
+            } => {
+                let unit = measurements
+                    .iter()
+                    .find_map(|x| x.raw.map(|(_, unit)| unit.name()))
+                    .unwrap_or("");
+                let values: Vec<String> = measurements
+                    .iter()
+                    .map(|measurement| match measurement.raw {
+                        Some((value, _)) if value.is_finite() => value.to_string(),
+                        // e.g. an unlimited rate, which has no numeric representation
+                        _ => measurement.value.clone(),
+                    })
+                    .collect();
+                let mut fields = vec![section, legend.trim(), unit];
+                for (measurement, value) in measurements.iter().zip(&values) {
+                    fields.push(value);
+                    fields.push(&measurement.comparison);
+                }
+                record(fields);
+            }
+        }
+    }
+    output
+}
+
+fn escape(field: &str, delimiter: char) -> String {
+    if delimiter == '\t' {
+        // TSV has no quoting, so just replace any characters that would break the format
+        field.replace(['\t', '\n', '\r'], " ")
+    } else if field.contains([delimiter, '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_owned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Goal, statistic::Measured, tables::Measurement, units::Unit};
+    use std::time::Duration;
+
+    #[test]
+    fn raw_values() {
+        let table = Table {
+            rows: vec![
+                Row::Heading("Opns (Operations)".to_owned()),
+                Row::Measurements {
+                    legend: "Total Opns".to_owned(),
+                    measurements: vec![
+                        Measurement::new(Some(Measured::count(1234.0, Goal::BiggerIsBetter)), None),
+                        Measurement::new(
+                            Some(Measured::count(1500.0, Goal::BiggerIsBetter)),
+                            Some(Measured::count(1000.0, Goal::BiggerIsBetter)),
+                        ),
+                    ],
+                },
+                Row::Measurements {
+                    legend: "p99".to_owned(),
+                    measurements: vec![
+                        Measurement::new(
+                            Some(Measured::duration(
+                                Duration::from_micros(1230),
+                                Goal::SmallerIsBetter,
+                            )),
+                            None,
+                        ),
+                        Measurement::new(None, None),
+                    ],
+                },
+                Row::Measurements {
+                    legend: "Target Opns Per Sec".to_owned(),
+                    measurements: vec![
+                        Measurement::new(
+                            Some(Measured {
+                                value: f64::INFINITY,
+                                unit: Unit::Count,
+                                display: "MAX".to_owned(),
+                                goal: Goal::BiggerIsBetter,
+                            }),
+                            None,
+                        ),
+                        Measurement::new(None, None),
+                    ],
+                },
+            ],
+            notes: vec![],
+        };
+        assert_eq!(
+            records(&table, ','),
+            "section,measurement,unit,column 0,column 0 comparison,column 1,column 1 comparison
+Opns (Operations),Total Opns,count,1234,,1500,+50.0%
+Opns (Operations),p99,seconds,0.00123,,,
+Opns (Operations),Target Opns Per Sec,count,MAX,,,
+"
+        );
+    }
+}
@@ -0,0 +1,101 @@
+use super::{Color, NoteKind, Row, Table};
+
+/// Renders the table as GitHub flavoured markdown, suitable for pasting into a PR or issue.
+///
+/// Markdown tables cannot contain headings, so each section of the table is rendered as its own markdown table.
+pub(crate) fn render(table: &Table) {
+    let column_labels = table.column_labels();
+    let mut rows = table.rows.iter().peekable();
+
+    // The first heading is the title of the whole table
+    if let Some(Row::Heading(title)) = rows.peek() {
+        println!("### {}", escape(title));
+        println!();
+        rows.next();
+    }
+
+    let mut in_table = false;
+    // Headings are only printed once we know their section contains measurements
+    let mut pending_heading = None;
+    for row in rows {
+        match row {
+            Row::Heading(heading) => {
+                if in_table {
+                    println!();
+                    in_table = false;
+                }
+                pending_heading = Some(heading);
+            }
+            // The column names are included in the header of each markdown table instead
+            Row::ColumnNames { .. } => {}
+            Row::Measurements {
+                legend,
+                measurements,
+            } => {
+                if let Some(heading) = pending_heading.take() {
+                    println!("**{}**", escape(heading));
+                    println!();
+                }
+                if !in_table {
+                    print_header(&column_labels);
+                    in_table = true;
+                }
+                print!("| {} |", escape(legend.trim()));
+                for measurement in measurements {
+                    let indicator = match measurement.color {
+                        Color::Good => " 🟢",
+                        Color::Bad => " 🔴",
+                        Color::Neutral => "",
+                    };
+                    if measurement.comparison.is_empty() {
+                        print!(" {} |", escape(&measurement.value));
+                    } else {
+                        print!(
+                            " {} ({}{}) |",
+                            escape(&measurement.value),
+                            escape(&measurement.comparison),
+                            indicator
+                        );
+                    }
+                }
+                println!();
+            }
+        }
+    }
+    if in_table {
+        println!();
+    }
+
+    for note in &table.notes {
+        let icon = match note.kind {
+            NoteKind::Error | NoteKind::Invalid => "⚠️",
+            NoteKind::Info => "ℹ️",
+        };
+        println!("{icon} **{}**", escape(&note.heading));
+        println!();
+        for message in &note.messages {
+            // Put each message in a code span so that any markdown in the message is not rendered
+            println!("- `{}`", message.replace('`', "'"));
+        }
+        if !note.messages.is_empty() {
+            println!();
+        }
+    }
+}
+
+fn print_header(column_labels: &[String]) {
+    print!("| |");
+    for label in column_labels {
+        print!(" {} |", escape(label));
+    }
+    println!();
+    print!("|--:|");
+    for _ in column_labels {
+        print!("--:|");
+    }
+    println!();
+}
+
+fn escape(text: &str) -> String {
+    text.replace('|', "\\|")
+}
@@ -0,0 +1,380 @@
+use super::{Color, NoteKind, Row, Table};
+use console::{Alignment, measure_text_width, pad_str, pad_str_with, style, truncate_str};
+use std::{fmt::Write, ops::Range};
+
+/// Column names are abbreviated when they are longer than both this and the values in their column
+const MIN_ABBREVIATED_WIDTH: usize = 16;
+
+/// Renders the table for display in a terminal with colors and box drawing characters.
+///
+/// When the table is wider than the terminal the columns are split into multiple pages that each fit within the terminal, repeating the legend column on every page.
+/// Long column names and legends are truncated and the full value is listed in a legend below the table.
+pub(crate) fn render(table: &Table) {
+    // if the user has set CARGO_TERM_COLOR to force cargo to use colors then they probably want us to use colors too
+    if std::env::var("CARGO_TERM_COLOR")
+        .map(|x| x.to_lowercase() == "always")
+        .unwrap_or(false)
+    {
+        console::set_colors_enabled(true);
+    }
+
+    print!("{}", format_table(table, terminal_width()));
+    render_notes(table);
+}
+
+/// Lays out the table to fit within `terminal_width`, followed by the full text of anything abbreviated
+fn format_table(table: &Table, terminal_width: Option<usize>) -> String {
+    let mut out = String::new();
+    let mut rows = table.rows.clone();
+    let column_count = table.column_count();
+
+    // the width of the comparison component of each column
+    let comparison_widths: Vec<usize> = (0..column_count)
+        .map(|i| {
+            rows.iter()
+                .map(|x| match x {
+                    Row::Heading(_) => 0,
+                    Row::ColumnNames { .. } => 0,
+                    Row::Measurements { measurements, .. } => {
+                        width(&measurements[i].comparison) + 1
+                    } // + 1 ensures we get separation from the previous column
+                })
+                .max()
+                .unwrap()
+        })
+        .collect();
+    // the width of the measurements in each column
+    let measurement_widths: Vec<usize> = (0..column_count)
+        .map(|i| {
+            rows.iter()
+                .map(|x| match x {
+                    Row::Measurements { measurements, .. } => {
+                        width(&measurements[i].value) + 1 // ensures we get seperation from the previous column
+                        + comparison_widths[i]
+                    }
+                    _ => 0,
+                })
+                .max()
+                .unwrap()
+        })
+        .collect();
+
+    let mut abbreviations = Abbreviations::default();
+    // the legend column is repeated on every page so it must leave room for the columns
+    let legend_limit = terminal_width.map(|x| x / 2).unwrap_or(usize::MAX);
+    for row in rows.iter_mut() {
+        match row {
+            Row::Heading(_) => {}
+            Row::ColumnNames { legend, names } => {
+                *legend = abbreviations.abbreviate(legend, legend_limit);
+                for (i, name) in names.iter_mut().enumerate() {
+                    // a column without any measurements has a width of 0
+                    let limit = measurement_widths[i]
+                        .saturating_sub(1)
+                        .max(MIN_ABBREVIATED_WIDTH);
+                    *name = abbreviations.abbreviate(name, limit);
+                }
+            }
+            Row::Measurements { legend, .. } => {
+                *legend = abbreviations.abbreviate(legend, legend_limit);
+            }
+        }
+    }
+
+    // the width of the legend column
+    let legend_width: usize = rows
+        .iter()
+        .skip(1) // skip the main heading because its big and its alignment doesnt matter
+        .map(|x| match x {
+            Row::Heading(heading) => width(heading),
+            Row::ColumnNames { legend, .. } => width(legend),
+            Row::Measurements { legend, .. } => width(legend),
+        })
+        .max()
+        .unwrap_or(10);
+    // the width of each entire column
+    let column_widths: Vec<usize> = (0..column_count)
+        .map(|i| {
+            rows.iter()
+                .map(|x| match x {
+                    Row::ColumnNames { names, .. } => width(&names[i]) + 1, // + 1 ensures we get separation from the previous column
+                    _ => 0,
+                })
+                .max()
+                .unwrap()
+                .max(measurement_widths[i])
+        })
+        .collect();
+
+    let pages = pages(legend_width, &column_widths, terminal_width);
+    for (page_index, page) in pages.iter().enumerate() {
+        if page_index > 0 {
+            writeln!(out).unwrap();
+        }
+        let total_width = legend_width + page.clone().map(|i| column_widths[i]).sum::<usize>();
+        for (row_index, row) in rows.iter().enumerate() {
+            match row {
+                Row::Heading(heading) => {
+                    let heading = if row_index == 0 && pages.len() > 1 {
+                        format!(
+                            "{heading} (columns {}-{} of {column_count})",
+                            page.start + 1,
+                            page.end
+                        )
+                    } else {
+                        heading.clone()
+                    };
+                    writeln!(
+                        out,
+                        "{}",
+                        style(pad_str_with(
+                            &format!("{} ", heading),
+                            total_width,
+                            Alignment::Left,
+                            None,
+                            '═'
+                        ))
+                        .yellow()
+                        .bold()
+                    )
+                    .unwrap()
+                }
+                Row::ColumnNames { legend, names } => {
+                    write!(
+                        out,
+                        "{}",
+                        style(pad_str(legend, legend_width, Alignment::Right, None))
+                            .yellow()
+                            .bold()
+                    )
+                    .unwrap();
+                    for i in page.clone() {
+                        write!(
+                            out,
+                            " {}",
+                            style(pad_str_with(
+                                &names[i],
+                                column_widths[i] - 1,
+                                Alignment::Center,
+                                None,
+                                '─',
+                            ))
+                        )
+                        .unwrap()
+                    }
+                    writeln!(out).unwrap()
+                }
+                Row::Measurements {
+                    legend,
+                    measurements,
+                } => {
+                    write!(
+                        out,
+                        "{}",
+                        style(pad_str(legend, legend_width, Alignment::Right, None))
+                            .yellow()
+                            .bold()
+                    )
+                    .unwrap();
+                    for i in page.clone() {
+                        let measurement = &measurements[i];
+                        let colorer = match measurement.color {
+                            Color::Good => |x| style(x).green(),
+                            Color::Bad => |x| style(x).red(),
+                            Color::Neutral => |x| style(x).dim(),
+                        };
+                        let contents = format!(
+                            "{}{}",
+                            measurement.value,
+                            colorer(pad_str(
+                                &measurement.comparison,
+                                comparison_widths[i],
+                                Alignment::Right,
+                                None
+                            )),
+                        );
+                        write!(
+                            out,
+                            "{}",
+                            pad_str(&contents, column_widths[i], Alignment::Right, None),
+                        )
+                        .unwrap();
+                    }
+                    writeln!(out).unwrap()
+                }
+            }
+        }
+    }
+
+    abbreviations.render(&mut out);
+    out
+}
+
+/// The width available for the table, None if the width is unlimited, e.g. because stdout is not a terminal.
+/// The `COLUMNS` environment variable overrides the detected width.
+pub(crate) fn terminal_width() -> Option<usize> {
+    match std::env::var("COLUMNS").ok().and_then(|x| x.parse().ok()) {
+        Some(columns) => Some(columns),
+        None => console::Term::stdout()
+            .size_checked()
+            .map(|(_rows, columns)| columns as usize),
+    }
+}
+
+/// Splits the columns into pages that each fit within the terminal alongside the legend column.
+/// Every page contains at least one column, even if that column does not fit.
+fn pages(
+    legend_width: usize,
+    column_widths: &[usize],
+    terminal_width: Option<usize>,
+) -> Vec<Range<usize>> {
+    let terminal_width = terminal_width.unwrap_or(usize::MAX);
+    let mut pages = vec![];
+    let mut start = 0;
+    let mut page_width = legend_width;
+    for (i, column_width) in column_widths.iter().enumerate() {
+        if i > start && page_width + column_width > terminal_width {
+            pages.push(start..i);
+            start = i;
+            page_width = legend_width;
+        }
+        page_width += column_width;
+    }
+    pages.push(start..column_widths.len());
+    pages
+}
+
+fn width(text: &str) -> usize {
+    measure_text_width(text)
+}
+
+/// Text that was truncated to fit in the table, listed below the table so the full text is still available.
+#[derive(Default)]
+struct Abbreviations(Vec<String>);
+
+impl Abbreviations {
+    /// Returns the text unchanged if it fits within the limit.
+    /// Otherwise the text is truncated and marked with a reference to its full text.
+    fn abbreviate(&mut self, text: &str, limit: usize) -> String {
+        if width(text) <= limit {
+            return text.to_owned();
+        }
+        let index = match self.0.iter().position(|x| x == text) {
+            Some(index) => index,
+            None => {
+                self.0.push(text.to_owned());
+                self.0.len() - 1
+            }
+        };
+        let tail = format!("…[{}]", index + 1);
+        truncate_str(text, limit, &tail).into_owned()
+    }
+
+    fn render(&self, out: &mut String) {
+        if !self.0.is_empty() {
+            writeln!(out, "{}", style("Abbreviations").yellow().bold()).unwrap();
+            for (i, text) in self.0.iter().enumerate() {
+                writeln!(out, "    [{}] {text}", i + 1).unwrap();
+            }
+        }
+    }
+}
+
+fn render_notes(table: &Table) {
+    let mut previous_kind = None;
+    for note in &table.notes {
+        if previous_kind.is_some_and(|x| x != &note.kind) {
+            // ensure different kinds of notes are kept apart
+            println!();
+        }
+        previous_kind = Some(&note.kind);
+
+        let heading = match note.kind {
+            NoteKind::Error | NoteKind::Invalid => style(&note.heading).red().bold(),
+            NoteKind::Info => style(&note.heading).blue().bold(),
+        };
+        println!("{heading}");
+        for (i, message) in note.messages.iter().enumerate() {
+            println!("    {i}.  {message}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Goal, statistic::Measured, tables::Measurement};
+
+    #[test]
+    fn column_names_without_measurements() {
+        // e.g. `results --include metric:<absent>` across several benches
+        let table = Table {
+            rows: vec![
+                Row::Heading("Results".to_owned()),
+                Row::ColumnNames {
+                    legend: "Unique Tags".to_owned(),
+                    names: vec![
+                        "db=kafka,topology=single".to_owned(),
+                        "db=kafka,topology=cluster3".to_owned(),
+                    ],
+                },
+            ],
+            notes: vec![],
+        };
+        assert_eq!(
+            format_plain(&table, Some(80)),
+            "Results ═════════════════════════════════════
+Unique Tags db=kafka,top…[1] db=kafka,top…[2]
+Abbreviations
+    [1] db=kafka,topology=single
+    [2] db=kafka,topology=cluster3
+"
+        );
+    }
+
+    #[test]
+    fn columns_split_into_pages() {
+        let measurement =
+            |value: f64| Measurement::new(Some(Measured::count(value, Goal::BiggerIsBetter)), None);
+        let table = Table {
+            rows: vec![
+                Row::Heading("Results".to_owned()),
+                Row::ColumnNames {
+                    legend: "name".to_owned(),
+                    names: vec!["a".to_owned(), "b".to_owned(), "c".to_owned()],
+                },
+                Row::Measurements {
+                    legend: "Opns Per Sec".to_owned(),
+                    measurements: vec![
+                        measurement(1000.0),
+                        measurement(2000.0),
+                        measurement(3000.0),
+                    ],
+                },
+            ],
+            notes: vec![],
+        };
+        assert_eq!(
+            format_plain(&table, Some(30)),
+            "Results (columns 1-2 of 3) 
+        name ──a─── ──b───
+Opns Per Sec 1,000  2,000 
+
+Results (columns 3-3 of 3) 
+        name ──c───
+Opns Per Sec 3,000 
+"
+        );
+        assert_eq!(
+            format_plain(&table, None),
+            "Results ═════════════════════════
+        name ──a─── ──b─── ──c───
+Opns Per Sec 1,000  2,000  3,000 
+"
+        );
+    }
+
+    fn format_plain(table: &Table, terminal_width: Option<usize>) -> String {
+        console::strip_ansi_codes(&format_table(table, terminal_width)).into_owned()
+    }
+}
@@ -0,0 +1,227 @@
+use anyhow::{Result, anyhow};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// The unit of a [`crate::Metric`] value.
+/// Windsock uses it to format the value in tables and charts consistently with its own results.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Unit {
+    /// A plain number, displayed with thousands separators, e.g. 1,234,567
+    Count,
+    /// A duration in seconds, displayed in the most readable of ns, µs, ms or s
+    Seconds,
+    /// A size in bytes, displayed in the most readable of B, KiB, MiB, GiB or TiB
+    Bytes,
+    /// A fraction, displayed as a percentage, e.g. 0.25 is displayed as 25.00%
+    Ratio,
+}
+
+impl Unit {
+    /// Formats a value measured in this unit for display
+    pub fn format(&self, value: f64) -> String {
+        match self {
+            Unit::Count => format_count(value),
+            // negative, non-finite or huge values cannot be represented as a Duration
+            Unit::Seconds => Duration::try_from_secs_f64(value)
+                .map(format_duration)
+                .unwrap_or_else(|_| format!("{value}s")),
+            Unit::Bytes => format_bytes(value),
+            Unit::Ratio => format!("{:.2}%", value * 100.0),
+        }
+    }
+
+    /// The name of the unit that unformatted values are measured in, e.g. `seconds`
+    pub(crate) fn name(&self) -> &'static str {
+        match self {
+            Unit::Count => "count",
+            Unit::Seconds => "seconds",
+            Unit::Bytes => "bytes",
+            Unit::Ratio => "ratio",
+        }
+    }
+}
+
+/// Formats the duration in the most readable of ns, µs, ms or s with about 3 significant figures, e.g. 850ns, 1.23ms or 12.3s
+pub(crate) fn format_duration(duration: Duration) -> String {
+    let nanos = duration.as_nanos();
+    if nanos < 1_000 {
+        format!("{nanos}ns")
+    } else {
+        scaled_units(nanos as f64 / 1_000.0, 1_000.0, &["µs", "ms", "s"])
+    }
+}
+
+/// Parses a duration such as 500ns, 250µs (or 250us), 10ms or 1.5s
+pub(crate) fn parse_duration(text: &str) -> Result<Duration> {
+    let text = text.trim();
+    let split = text
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(text.len());
+    let (number, unit) = text.split_at(split);
+    let scale = match unit.trim() {
+        "ns" => 1e-9,
+        "µs" | "us" => 1e-6,
+        "ms" => 1e-3,
+        "s" => 1.0,
+        _ => {
+            return Err(anyhow!(
+                "Invalid duration {text:?}, expected a number followed by ns, µs, us, ms or s, e.g. 10ms"
+            ));
+        }
+    };
+    let number: f64 = number.parse().map_err(|_| {
+        anyhow!("Invalid duration {text:?}, expected a number followed by a unit, e.g. 10ms")
+    })?;
+    Duration::try_from_secs_f64(number * scale)
+        .map_err(|_| anyhow!("Invalid duration {text:?}, it is too large"))
+}
+
+/// Formats a whole number of seconds in hours, minutes and seconds, e.g. 45s, 2m 30s or 1h 5m 0s
+pub(crate) fn format_seconds(seconds: u64) -> String {
+    let (hours, minutes, seconds) = (seconds / 3600, seconds / 60 % 60, seconds % 60);
+    if hours > 0 {
+        format!("{hours}h {minutes}m {seconds}s")
+    } else if minutes > 0 {
+        format!("{minutes}m {seconds}s")
+    } else {
+        format!("{seconds}s")
+    }
+}
+
+/// Formats the number with thousands separators, e.g. 1,234,567 or 1,234.57
+pub(crate) fn format_count(value: f64) -> String {
+    if !value.is_finite() {
+        return value.to_string();
+    }
+    let text = if value.fract() == 0.0 {
+        format!("{value:.0}")
+    } else {
+        format!("{value:.2}")
+    };
+    let (sign, text) = match text.strip_prefix('-') {
+        Some(text) => ("-", text),
+        None => ("", text.as_str()),
+    };
+    let (integer, fraction) = match text.find('.') {
+        Some(i) => text.split_at(i),
+        None => (text, ""),
+    };
+
+    let mut grouped = String::with_capacity(integer.len() + integer.len() / 3);
+    for (i, digit) in integer.chars().enumerate() {
+        if i > 0 && (integer.len() - i) % 3 == 0 {
+            grouped.push(',');
+        }
+        grouped.push(digit);
+    }
+    format!("{sign}{grouped}{fraction}")
+}
+
+fn format_bytes(value: f64) -> String {
+    if value.abs() < 1024.0 {
+        return format!("{value}B");
+    }
+    scaled_units(value / 1024.0, 1024.0, &["KiB", "MiB", "GiB", "TiB"])
+}
+
+/// Displays the value in the first of `units` that it is less than `base` of once rounded, each unit being `base` times the previous one.
+/// Deciding after rounding ensures that e.g. 999.9µs is displayed as 1.00ms rather than 1000µs.
+fn scaled_units(mut value: f64, base: f64, units: &[&str]) -> String {
+    let (last, units) = units.split_last().unwrap();
+    for unit in units {
+        let rounded = significant(value);
+        if rounded.parse::<f64>().unwrap_or(value).abs() < base {
+            return format!("{rounded}{unit}");
+        }
+        value /= base;
+    }
+    format!("{}{last}", significant(value))
+}
+
+/// Formats the value with about 3 significant figures.
+/// The precision is decided after rounding so that e.g. 9.999 is displayed as 10.0 rather than 10.00
+fn significant(value: f64) -> String {
+    let decimals = |value: f64| {
+        if value.abs() < 10.0 {
+            2
+        } else if value.abs() < 100.0 {
+            1
+        } else {
+            0
+        }
+    };
+    let text = format!("{value:.*}", decimals(value));
+    match text.parse::<f64>() {
+        Ok(rounded) if decimals(rounded) != decimals(value) => {
+            format!("{value:.*}", decimals(rounded))
+        }
+        _ => text,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn format_seconds_unit() {
+        assert_eq!(Unit::Seconds.format(0.0015), "1.50ms");
+        assert_eq!(Unit::Seconds.format(12.34), "12.3s");
+        // values just below a unit boundary switch unit once rounded
+        assert_eq!(Unit::Seconds.format(0.000_000_999), "999ns");
+        assert_eq!(Unit::Seconds.format(0.000_999_999), "1.00ms");
+        assert_eq!(Unit::Seconds.format(0.999_9), "1.00s");
+        assert_eq!(Unit::Seconds.format(0.000_999_4), "999µs");
+        assert_eq!(Unit::Seconds.format(0.009_999), "10.0ms");
+        assert_eq!(Unit::Seconds.format(0.099_99), "100ms");
+        assert_eq!(Unit::Seconds.format(999.9), "1000s");
+        // values that can not be represented as a Duration must not panic
+        assert_eq!(Unit::Seconds.format(-1.0), "-1s");
+        assert_eq!(Unit::Seconds.format(f64::NAN), "NaNs");
+        assert_eq!(Unit::Seconds.format(f64::INFINITY), "infs");
+        assert_eq!(Unit::Seconds.format(1e300), format!("{}s", 1e300));
+    }
+
+    #[test]
+    fn format_other_units() {
+        assert_eq!(Unit::Count.format(1234567.0), "1,234,567");
+        assert_eq!(Unit::Count.format(-1234.567), "-1,234.57");
+        assert_eq!(Unit::Bytes.format(512.0), "512B");
+        assert_eq!(Unit::Bytes.format(1536.0), "1.50KiB");
+        // values just below a unit boundary switch unit once rounded
+        assert_eq!(Unit::Bytes.format(1023.9 * 1024.0), "1.00MiB");
+        assert_eq!(Unit::Bytes.format(1023.4 * 1024.0), "1023KiB");
+        assert_eq!(Unit::Bytes.format(9.999 * 1024.0), "10.0KiB");
+        assert_eq!(Unit::Bytes.format(1023.9 * 1024.0_f64.powi(4)), "1024TiB");
+        assert_eq!(Unit::Ratio.format(0.25), "25.00%");
+    }
+
+    #[test]
+    fn parse_duration_units() {
+        assert_eq!(parse_duration("500ns").unwrap(), Duration::from_nanos(500));
+        assert_eq!(parse_duration("250µs").unwrap(), Duration::from_micros(250));
+        assert_eq!(parse_duration("250us").unwrap(), Duration::from_micros(250));
+        assert_eq!(parse_duration(" 10ms ").unwrap(), Duration::from_millis(10));
+        assert_eq!(parse_duration("1.5s").unwrap(), Duration::from_millis(1500));
+        assert_eq!(
+            parse_duration("1.5 s").unwrap(),
+            Duration::from_millis(1500)
+        );
+    }
+
+    #[test]
+    fn parse_duration_errors() {
+        for text in ["", "10", "ms", "10m", "1.2.3s", "-5s", "1e3s"] {
+            assert!(parse_duration(text).is_err(), "{text} parsed");
+        }
+        // too large to be a Duration
+        assert!(parse_duration(&format!("{}s", "9".repeat(30))).is_err());
+    }
+
+    #[test]
+    fn format_whole_seconds() {
+        assert_eq!(format_seconds(45), "45s");
+        assert_eq!(format_seconds(150), "2m 30s");
+        assert_eq!(format_seconds(3900), "1h 5m 0s");
+    }
+}
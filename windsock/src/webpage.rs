@@ -0,0 +1,485 @@
+use crate::{
+    Metric, Unit,
+    bench::Tags,
+    data::{windsock_path, write_atomic},
+    filter::Filter,
+    report::{Percentile, Percentiles, ReportArchive},
+};
+use anyhow::{Context, Result, anyhow};
+use serde::Serialize;
+use std::{collections::BTreeMap, path::PathBuf, time::Duration};
+use strum::IntoEnumIterator;
+use time::OffsetDateTime;
+
+const TEMPLATE: &str = include_str!("webpage.html");
+const DATA_PLACEHOLDER: &str = "/*WINDSOCK_DATA*/null";
+
+/// The percentiles that can be selected in charts of latency over time
+const EACH_SECOND_PERCENTILES: [Percentile; 5] = [
+    Percentile::P50,
+    Percentile::P90,
+    Percentile::P99,
+    Percentile::P99_9,
+    Percentile::Max,
+];
+
+#[derive(Serialize)]
+struct Page {
+    title: String,
+    generated_at: String,
+    groups: Vec<Group>,
+}
+
+/// A set of benches that are charted together
+#[derive(Serialize)]
+struct Group {
+    name: String,
+    benches: Vec<BenchMessages>,
+    charts: Vec<Chart>,
+}
+
+#[derive(Serialize)]
+struct BenchMessages {
+    name: String,
+    running_in_release: bool,
+    error_messages: Vec<String>,
+    info_messages: Vec<String>,
+}
+
+#[derive(Serialize)]
+struct Chart {
+    title: String,
+    unit: &'static str,
+    /// When set the x values are indexes into these labels instead of seconds
+    x_labels: Option<Vec<String>>,
+    /// The user can switch between variants of a chart, e.g. to select which percentile to display
+    variants: Vec<Variant>,
+}
+
+#[derive(Serialize)]
+struct Variant {
+    name: String,
+    series: Vec<Series>,
+}
+
+#[derive(Serialize)]
+struct Series {
+    name: String,
+    baseline: bool,
+    values: Vec<Option<f64>>,
+}
+
+/// The benches in a group along with the label that distinguishes them from the other benches in the group
+struct Entry {
+    label: String,
+    current: ReportArchive,
+    baseline: Option<ReportArchive>,
+}
+
+pub fn generate_webpage(
+    ignore_baseline: bool,
+    filter: &str,
+    compare_tags: &[String],
+) -> Result<()> {
+    let filter = Filter::from_query(filter)
+        .with_context(|| format!("Failed to parse tag filter from {:?}", filter))?;
+
+    // Benches that differ only by the `compare_tags` are placed in the same group
+    let mut groups: BTreeMap<String, Vec<Entry>> = BTreeMap::new();
+    for name in ReportArchive::reports_in_last_run() {
+        let tags = Tags::from_name(&name)?;
+        if !filter.matches(&tags) {
+            continue;
+        }
+        let (compared, common): (Vec<_>, Vec<_>) = tags
+            .0
+            .iter()
+            .partition(|(key, _)| compare_tags.contains(key));
+        let common = Tags(
+            common
+                .into_iter()
+                .map(|(k, v)| (k.clone(), v.clone()))
+                .collect(),
+        );
+        let compared = Tags(
+            compared
+                .into_iter()
+                .map(|(k, v)| (k.clone(), v.clone()))
+                .collect(),
+        );
+        let label = if compared.0.is_empty() {
+            "current".to_owned()
+        } else {
+            compared.get_name()
+        };
+        groups.entry(common.get_name()).or_default().push(Entry {
+            label,
+            current: ReportArchive::load(&name)?,
+            baseline: if ignore_baseline {
+                None
+            } else {
+                ReportArchive::load_baseline(&name)?
+            },
+        });
+    }
+
+    if groups.is_empty() {
+        return Err(anyhow!(
+            "No benches in the last run matched the specified filter"
+        ));
+    }
+
+    let page = Page {
+        title: "Windsock Results".to_owned(),
+        generated_at: OffsetDateTime::now_utc().to_string(),
+        groups: groups
+            .into_iter()
+            .map(|(name, entries)| Group {
+                name,
+                benches: entries
+                    .iter()
+                    .map(|entry| BenchMessages {
+                        name: entry.current.tags.get_name(),
+                        running_in_release: entry.current.running_in_release,
+                        error_messages: entry.current.error_messages.clone(),
+                        info_messages: entry.current.info_messages.clone(),
+                    })
+                    .collect(),
+                charts: charts(&entries),
+            })
+            .collect(),
+    };
+
+    let json = serde_json::to_string(&page)
+        .unwrap()
+        // ensure the data can never close the script tag it is embedded in
+        .replace("</", "<\\/");
+    let html = TEMPLATE.replace(DATA_PLACEHOLDER, &json);
+
+    let path = webpage_path();
+    std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+    write_atomic(&path, html.as_bytes())
+        .with_context(|| format!("Failed to write webpage to {path:?}"))?;
+    println!("Webpage written to {}", path.display());
+    Ok(())
+}
+
+fn webpage_path() -> PathBuf {
+    windsock_path().join("webpage").join("index.html")
+}
+
+fn charts(entries: &[Entry]) -> Vec<Chart> {
+    let mut charts = vec![];
+
+    if entries
+        .iter()
+        .any(|x| x.current.operations_report.is_some())
+    {
+        charts.push(each_second_chart(
+            "Operations Each Second",
+            "ops/s",
+            entries,
+            |archive| {
+                archive
+                    .operations_report
+                    .as_ref()
+                    .map(|x| x.total_each_second.iter().map(|x| *x as f64).collect())
+            },
+        ));
+        if entries.iter().any(|x| {
+            x.current
+                .operations_report
+                .as_ref()
+                .is_some_and(|x| !x.requested_each_second.is_empty())
+        }) {
+            charts.push(each_second_chart(
+                "Target Operations Each Second",
+                "ops/s",
+                entries,
+                |archive| {
+                    archive
+                        .operations_report
+                        .as_ref()
+                        .map(|x| x.requested_each_second.iter().map(|x| *x as f64).collect())
+                },
+            ));
+        }
+        charts.push(latency_each_second_chart(
+            "Operation Latency Each Second",
+            entries,
+            |archive| {
+                archive
+                    .operations_report
+                    .as_ref()
+                    .map(|x| x.time_percentiles_each_second.as_slice())
+            },
+        ));
+        charts.push(percentiles_chart(
+            "Operation Latency Percentiles",
+            entries,
+            |archive| {
+                archive
+                    .operations_report
+                    .as_ref()
+                    .map(|x| x.time_percentiles)
+            },
+        ));
+    }
+
+    if entries.iter().any(|x| x.current.pubsub_report.is_some()) {
+        charts.push(each_second_chart(
+            "Produce Each Second",
+            "ops/s",
+            entries,
+            |archive| {
+                archive
+                    .pubsub_report
+                    .as_ref()
+                    .map(|x| x.produce_each_second.iter().map(|x| *x as f64).collect())
+            },
+        ));
+        if entries.iter().any(|x| {
+            x.current
+                .pubsub_report
+                .as_ref()
+                .is_some_and(|x| !x.requested_produce_each_second.is_empty())
+        }) {
+            charts.push(each_second_chart(
+                "Target Produce Each Second",
+                "ops/s",
+                entries,
+                |archive| {
+                    archive.pubsub_report.as_ref().map(|x| {
+                        x.requested_produce_each_second
+                            .iter()
+                            .map(|x| *x as f64)
+                            .collect()
+                    })
+                },
+            ));
+        }
+        charts.push(each_second_chart(
+            "Consume Each Second",
+            "ops/s",
+            entries,
+            |archive| {
+                archive
+                    .pubsub_report
+                    .as_ref()
+                    .map(|x| x.consume_each_second.iter().map(|x| *x as f64).collect())
+            },
+        ));
+        charts.push(each_second_chart(
+            "Total Backlog Each Second",
+            "",
+            entries,
+            |archive| {
+                archive
+                    .pubsub_report
+                    .as_ref()
+                    .map(|x| x.backlog_each_second.iter().map(|x| *x as f64).collect())
+            },
+        ));
+        charts.push(latency_each_second_chart(
+            "Produce Latency Each Second",
+            entries,
+            |archive| {
+                archive
+                    .pubsub_report
+                    .as_ref()
+                    .map(|x| x.produce_time_percentiles_each_second.as_slice())
+            },
+        ));
+        charts.push(percentiles_chart(
+            "Produce Latency Percentiles",
+            entries,
+            |archive| {
+                archive
+                    .pubsub_report
+                    .as_ref()
+                    .map(|x| x.produce_time_percentiles)
+            },
+        ));
+        charts.push(percentiles_chart(
+            "Consume Latency Percentiles",
+            entries,
+            |archive| {
+                archive
+                    .pubsub_report
+                    .as_ref()
+                    .map(|x| x.consume_time_percentiles)
+            },
+        ));
+    }
+
+    let mut metric_names: Vec<&str> = vec![];
+    for entry in entries {
+        for metric in &entry.current.metrics {
+            if !matches!(metric, Metric::Total { .. }) && !metric_names.contains(&metric.name()) {
+                metric_names.push(metric.name());
+            }
+        }
+    }
+    for name in metric_names {
+        let find = |archive: &ReportArchive| -> Option<Metric> {
+            archive.metrics.iter().find(|x| x.name() == name).cloned()
+        };
+        match entries.iter().find_map(|x| find(&x.current)) {
+            Some(Metric::EachSecond { unit, .. }) => {
+                let (chart_unit, scale) = chart_unit(unit);
+                charts.push(each_second_chart(
+                    &format!("{name} Each Second"),
+                    chart_unit,
+                    entries,
+                    |archive| match find(archive) {
+                        Some(Metric::EachSecond { values, .. }) => {
+                            Some(values.iter().map(|x| x * scale).collect())
+                        }
+                        _ => None,
+                    },
+                ));
+            }
+            Some(Metric::LatencyPercentiles { values, .. }) => {
+                let x_labels: Vec<String> = values.iter().map(|x| x.quantile.clone()).collect();
+                charts.push(Chart {
+                    title: format!("{name} Percentiles"),
+                    unit: "ms",
+                    variants: vec![Variant {
+                        name: String::new(),
+                        series: series(entries, |archive| match find(archive) {
+                            Some(Metric::LatencyPercentiles { values, .. }) => x_labels
+                                .iter()
+                                .map(|label| {
+                                    values
+                                        .iter()
+                                        .find(|x| &x.quantile == label)
+                                        .map(|x| duration_ms(x.value))
+                                })
+                                .collect(),
+                            _ => vec![],
+                        }),
+                    }],
+                    x_labels: Some(x_labels),
+                });
+            }
+            _ => {}
+        }
+    }
+
+    charts
+}
+
+/// Creates a current and a baseline series for every entry
+fn series<F: Fn(&ReportArchive) -> Vec<Option<f64>>>(entries: &[Entry], f: F) -> Vec<Series> {
+    let mut series = vec![];
+    for entry in entries {
+        series.push(Series {
+            name: entry.label.clone(),
+            baseline: false,
+            values: f(&entry.current),
+        });
+        if let Some(baseline) = &entry.baseline {
+            series.push(Series {
+                name: format!("{} baseline", entry.label),
+                baseline: true,
+                values: f(baseline),
+            });
+        }
+    }
+    series.retain(|x| !x.values.is_empty());
+    series
+}
+
+fn each_second_chart<F: Fn(&ReportArchive) -> Option<Vec<f64>>>(
+    title: &str,
+    unit: &'static str,
+    entries: &[Entry],
+    f: F,
+) -> Chart {
+    Chart {
+        title: title.to_owned(),
+        unit,
+        x_labels: None,
+        variants: vec![Variant {
+            name: String::new(),
+            series: series(entries, |archive| {
+                f(archive)
+                    .map(|values| values.into_iter().map(Some).collect())
+                    .unwrap_or_default()
+            }),
+        }],
+    }
+}
+
+fn latency_each_second_chart<F: Fn(&ReportArchive) -> Option<&[Percentiles]>>(
+    title: &str,
+    entries: &[Entry],
+    f: F,
+) -> Chart {
+    Chart {
+        title: title.to_owned(),
+        unit: "ms",
+        x_labels: None,
+        variants: EACH_SECOND_PERCENTILES
+            .iter()
+            .map(|percentile| {
+                let i = *percentile as usize;
+                Variant {
+                    name: percentile_label(percentile),
+                    series: series(entries, |archive| {
+                        f(archive)
+                            .map(|each_second| {
+                                each_second
+                                    .iter()
+                                    .map(|percentiles| Some(duration_ms(percentiles[i])))
+                                    .collect()
+                            })
+                            .unwrap_or_default()
+                    }),
+                }
+            })
+            .collect(),
+    }
+}
+
+fn percentiles_chart<F: Fn(&ReportArchive) -> Option<Percentiles>>(
+    title: &str,
+    entries: &[Entry],
+    f: F,
+) -> Chart {
+    Chart {
+        title: title.to_owned(),
+        unit: "ms",
+        x_labels: Some(Percentile::iter().map(|x| percentile_label(&x)).collect()),
+        variants: vec![Variant {
+            name: String::new(),
+            series: series(entries, |archive| {
+                f(archive)
+                    .map(|percentiles| percentiles.iter().map(|x| Some(duration_ms(*x))).collect())
+                    .unwrap_or_default()
+            }),
+        }],
+    }
+}
+
+/// The unit displayed by the chart for a metric and the factor to convert the metric values into that unit
+fn chart_unit(unit: Unit) -> (&'static str, f64) {
+    match unit {
+        Unit::Count => ("", 1.0),
+        Unit::Seconds => ("ms", 1000.0),
+        Unit::Bytes => ("B", 1.0),
+        Unit::Ratio => ("%", 100.0),
+    }
+}
+
+fn percentile_label(percentile: &Percentile) -> String {
+    let name = percentile.name().trim();
+    match name {
+        "Min" | "Max" => name.to_owned(),
+        _ => format!("p{name}"),
+    }
+}
+
+fn duration_ms(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}